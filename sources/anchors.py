@@ -0,0 +1,228 @@
+"""Write the workbook sources/anchors.xlsx read by the integration tests.
+
+    python3 sources/anchors.py sources/anchors.xlsx
+
+The workbook is written with the standard library only, and always with the
+same bytes, so a change of the sheets below is the whole change of the
+fixture. Its sheets are:
+
+- Overview: two categories over a product per row, with the defined names
+  "Products" and "MechanicalHeader" and the Excel table "Grades" (anchored
+  labels, label matching, lookup of the pdf files)
+- Blocks: two categories with the same parameter labels (labels scoped
+  "within" a category or in a "range")
+- Merged: category titles merged over their columns, and a note merged in
+  the second category (category spans)
+- Values: typed values, dates and Excel errors (number formats and error
+  cells)
+- Transposed: the Overview sheet with a product per column (columns
+  orientation)
+- Headers: parameters with a second level of names (parameter groups)
+- Stacked: the Headers sheet with a category written under another one
+- Products: a line of fields per product (lookup worksheet)
+- Variants: the Overview sheet with a variant row (product variants)
+- Unnamed: a product under a category without parameter labels, and
+  Untitled: a product under parameter labels without a category (products
+  read from several sheets)
+
+A cell is a string, a number, a boolean, ("date", serial) or ("err", text).
+"""
+
+import sys
+import zipfile
+from xml.sax.saxutils import escape
+
+OVERVIEW = [
+    ["", "", "Properties", "", "Mechanical Properties", ""],
+    ["", "Parameters", "Density", "MFR", "Tensile strength", "Elongation"],
+    ["Grade", "Unit", "g/cm³", "g/10 min", "MPa", "%"],
+    ["BIOPLAST 400", "", 1.25, 5, 20, 300],
+    ["BIOPLAST 500", "", 1.3, 4, 25, 250],
+]
+
+BLOCKS = [
+    ["", "Properties", "", "Mechanical Properties", ""],
+    ["", "Density", "MFR", "Tensile strength", "Elongation"],
+    ["", "Unit", "", "Unit", ""],
+    ["BIOPLAST 400", 1.25, 5, 20, 300],
+]
+
+MERGED = [
+    ["", "Properties", "", "Mechanical Properties", "see note", ""],
+    ["Parameters", "Density", "MFR", "Tensile strength", "Elongation", "Impact"],
+    ["BIOPLAST 400", 1.25, 5, 20, 300, 12],
+]
+
+VALUES = [
+    ["", "Properties", "", "", "", "", ""],
+    ["Parameters", "Density", "MFR", "Tensile modulus", "Compostable", "Updated", "Batch"],
+    ["Unit", "g/cm³", "g/10 min", "MPa", "", "", ""],
+    ["BIOPLAST 400", 1.2500000000000002, 3.0, 12345.678, True, ("date", 45000), 1234567],
+    ["BIOPLAST 500", ("err", "#N/A"), ("err", "#DIV/0!"), 2100, False, ("date", 45001), 7654321],
+]
+
+HEADERS = [
+    ["", "Properties", "", "Mechanical Properties", "", "", ""],
+    ["Parameters", "Density", "MFR", "Tensile strength", "", "Elongation", ""],
+    ["Direction", "", "", "MD", "TD", "MD", "TD"],
+    ["Unit", "g/cm³", "g/10 min", "MPa", "MPa", "%", "%"],
+    ["BIOPLAST 400", 1.25, 5, 20, 18, 300, 280],
+]
+
+STACKED = [row + ["", ""] for row in HEADERS]
+STACKED[4][7:9] = [40, 38]
+STACKED += [
+    [""] * 7 + ["Tear resistance", ""],
+    [""] * 7 + ["Tear", ""],
+    [""] * 7 + ["MD", "TD"],
+    [""] * 7 + ["N", "N"],
+]
+
+VARIANTS = OVERVIEW + [["BIOPLAST 400 HF", "", "", 8, "", ""]]
+
+PRODUCTS = [
+    ["Product name", "Description", "Image", "Certification"],
+    ["BIOPLAST 400", "Film grade for compostable bags", "bioplast400.png", "DIN-7W0123"],
+    ["BIOPLAST 500", "Injection moulding grade", "", ("err", "#N/A")],
+]
+
+UNNAMED = [
+    ["", "Properties", ""],
+    ["", "Density", "MFR"],
+    ["BIOPLAST 600", 1.2, 6],
+]
+
+UNTITLED = [
+    ["Parameters", "Density", "MFR"],
+    ["BIOPLAST 700", 1.1, 7],
+]
+
+TRANSPOSED = [list(column) for column in zip(*OVERVIEW)]
+
+SHEETS = [
+    # (name, rows, merged cells, tables as (name, range, column names))
+    ("Overview", OVERVIEW, [], [("Grades", "A3:A5", ["Grade"])]),
+    ("Blocks", BLOCKS, [], []),
+    ("Merged", MERGED, ["B1:C1", "D1:F1"], []),
+    ("Values", VALUES, [], []),
+    ("Transposed", TRANSPOSED, ["A3:A4"], []),
+    ("Headers", HEADERS, ["B1:C1", "D1:G1", "D2:E2"], []),
+    ("Products", PRODUCTS, [], []),
+    ("Variants", VARIANTS, [], []),
+    ("Stacked", STACKED, ["B1:C1", "D1:G1", "D2:E2", "H6:I6", "H7:I7"], []),
+    ("Unnamed", UNNAMED, [], []),
+    ("Untitled", UNTITLED, [], []),
+]
+
+NAMES = [
+    ("Products", "Overview!$A$4:$A$5"),
+    ("MechanicalHeader", "Overview!$E$1"),
+]
+
+MAIN = "http://schemas.openxmlformats.org/spreadsheetml/2006/main"
+RELATIONSHIPS = "http://schemas.openxmlformats.org/package/2006/relationships"
+DOCUMENT = "http://schemas.openxmlformats.org/officeDocument/2006/relationships"
+CONTENT = "application/vnd.openxmlformats-officedocument.spreadsheetml"
+HEADER = '<?xml version="1.0" encoding="UTF-8" standalone="yes"?>\n'
+
+
+def column_name(column):
+    name = ""
+    column += 1
+    while column:
+        column, rest = divmod(column - 1, 26)
+        name = chr(65 + rest) + name
+    return name
+
+
+def cell_xml(ref, value):
+    if value == "" or value is None:
+        return ""
+    if isinstance(value, bool):
+        return f'<c r="{ref}" t="b"><v>{int(value)}</v></c>'
+    if isinstance(value, (int, float)):
+        return f'<c r="{ref}"><v>{value}</v></c>'
+    if isinstance(value, tuple) and value[0] == "err":
+        return f'<c r="{ref}" t="e"><v>{escape(value[1])}</v></c>'
+    if isinstance(value, tuple) and value[0] == "date":
+        return f'<c r="{ref}" s="1"><v>{value[1]}</v></c>'
+    return f'<c r="{ref}" t="inlineStr"><is><t>{escape(value)}</t></is></c>'
+
+
+def sheet_xml(rows, merges, tables):
+    data = "".join(
+        f'<row r="{r + 1}">'
+        + "".join(cell_xml(f"{column_name(c)}{r + 1}", v) for c, v in enumerate(row))
+        + "</row>"
+        for r, row in enumerate(rows)
+    )
+    xml = f'{HEADER}<worksheet xmlns="{MAIN}" xmlns:r="{DOCUMENT}"><sheetData>{data}</sheetData>'
+    if merges:
+        cells = "".join(f'<mergeCell ref="{m}"></mergeCell>' for m in merges)
+        xml += f'<mergeCells count="{len(merges)}">{cells}</mergeCells>'
+    if tables:
+        parts = "".join(f'<tablePart r:id="rIdT{i + 1}"></tablePart>' for i in range(len(tables)))
+        xml += f'<tableParts count="{len(tables)}">{parts}</tableParts>'
+    return xml + "</worksheet>"
+
+
+def table_xml(number, name, ref, columns):
+    cells = "".join(f'<tableColumn id="{i + 1}" name="{escape(c)}"></tableColumn>' for i, c in enumerate(columns))
+    return (
+        f'{HEADER}<table xmlns="{MAIN}" id="{number}" name="{name}" displayName="{name}" ref="{ref}">'
+        f'<tableColumns count="{len(columns)}">{cells}</tableColumns></table>'
+    )
+
+
+def relationships(items):
+    links = "".join(f'<Relationship Id="{i}" Type="{DOCUMENT}/{t}" Target="{target}"></Relationship>' for i, t, target in items)
+    return f'{HEADER}<Relationships xmlns="{RELATIONSHIPS}">{links}</Relationships>'
+
+
+def build(path):
+    files = {}
+    types = [
+        ("/xl/workbook.xml", f"{CONTENT}.sheet.main+xml"),
+        ("/xl/styles.xml", f"{CONTENT}.styles+xml"),
+    ]
+    sheets = "".join(
+        f'<sheet name="{escape(name)}" sheetId="{i + 1}" r:id="rId{i + 1}"></sheet>' for i, (name, *_) in enumerate(SHEETS)
+    )
+    names = "".join(f'<definedName name="{escape(n)}">{escape(f)}</definedName>' for n, f in NAMES)
+    files["xl/workbook.xml"] = (
+        f'{HEADER}<workbook xmlns="{MAIN}" xmlns:r="{DOCUMENT}"><sheets>{sheets}</sheets>'
+        f"<definedNames>{names}</definedNames></workbook>"
+    )
+    files["xl/styles.xml"] = (
+        f'{HEADER}<styleSheet xmlns="{MAIN}"><numFmts count="1"><numFmt numFmtId="164" formatCode="yyyy-mm-dd"></numFmt></numFmts>'
+        '<cellXfs count="2"><xf numFmtId="0"></xf><xf numFmtId="164" applyNumberFormat="1"></xf></cellXfs></styleSheet>'
+    )
+    links = [(f"rId{i + 1}", "worksheet", f"worksheets/sheet{i + 1}.xml") for i in range(len(SHEETS))]
+    files["xl/_rels/workbook.xml.rels"] = relationships(links + [("rIdS", "styles", "styles.xml")])
+    number = 0
+    for i, (_, rows, merges, tables) in enumerate(SHEETS):
+        files[f"xl/worksheets/sheet{i + 1}.xml"] = sheet_xml(rows, merges, tables)
+        types.append((f"/xl/worksheets/sheet{i + 1}.xml", f"{CONTENT}.worksheet+xml"))
+        links = []
+        for k, (name, ref, columns) in enumerate(tables):
+            number += 1
+            files[f"xl/tables/table{number}.xml"] = table_xml(number, name, ref, columns)
+            types.append((f"/xl/tables/table{number}.xml", f"{CONTENT}.table+xml"))
+            links.append((f"rIdT{k + 1}", "table", f"../tables/table{number}.xml"))
+        if links:
+            files[f"xl/worksheets/_rels/sheet{i + 1}.xml.rels"] = relationships(links)
+    overrides = "".join(f'<Override PartName="{p}" ContentType="{t}"></Override>' for p, t in types)
+    files["[Content_Types].xml"] = (
+        f'{HEADER}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">'
+        f'<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"></Default>'
+        f'<Default Extension="xml" ContentType="application/xml"></Default>{overrides}</Types>'
+    )
+    files["_rels/.rels"] = relationships([("rId1", "officeDocument", "xl/workbook.xml")])
+    with zipfile.ZipFile(path, "w", zipfile.ZIP_DEFLATED) as workbook:
+        for name in sorted(files):
+            # a fixed date keeps the same bytes for the same sheets
+            workbook.writestr(zipfile.ZipInfo(name, (1980, 1, 1, 0, 0, 0)), files[name], zipfile.ZIP_DEFLATED)
+
+
+if __name__ == "__main__":
+    build(sys.argv[1] if len(sys.argv) > 1 else "sources/anchors.xlsx")
@@ -0,0 +1,91 @@
+use serde::Deserialize;
+
+/// Scale of the logo when none is given
+const DEFAULT_LOGO_SCALE: f32 = 0.20;
+
+const BIOTEC_DISCLAIMER: &str = "\\textbf{Disclaimer} This information and our technical advice - whether verbal, in writing or by way of trials - are given in good faith but without warranty, and this also applies where proprietary rights of third parties are involved. Our advice does not release you from the obligation to check its validity and to test our products as to their suitability for the intended processes and uses. The application, use and processing of our products and the products manufactured by you on the basis of our technical advice are beyond our control and, therefore, entirely your own responsibility. Our products are sold in accordance with our General Conditions of Sale and Delivery.";
+
+const BIOTEC_FOOTER: &str = "BIOTEC Biologische Naturverpackungen GmbH \\& Co. KG · Werner-Heisenberg-Str. 32 · D.46446 Emmerich \\hfill \\textbf{T} +49 2822 92510\\qquad \\textbf{W} biotec.de";
+
+/// The brand written on the documents. The texts are written as LaTeX.
+/// Every option not set in a pdf file is taken from the branding of the
+/// config, and then from the BIOTEC branding.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Branding {
+    /// Image of the logo, in the assets directory, without its extension
+    pub logo: Option<String>,
+    pub logo_scale: Option<f32>,
+    /// Title and author of the pdf metadata
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// Heading of the product pages, above the product name
+    pub heading: Option<String>,
+    /// Text at the bottom of the pages, none when empty
+    pub disclaimer: Option<String>,
+    /// Line under the disclaimer, like the company address
+    pub footer: Option<String>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            logo: Some(String::from("biotec")),
+            logo_scale: Some(DEFAULT_LOGO_SCALE),
+            title: Some(String::from("Template")),
+            author: Some(String::from("Biotec")),
+            heading: Some(String::from("Preliminary Data Sheet")),
+            disclaimer: Some(String::from(BIOTEC_DISCLAIMER)),
+            footer: Some(String::from(BIOTEC_FOOTER)),
+        }
+    }
+}
+
+impl Branding {
+    /// Return this branding completed with the options of `default`
+    pub fn or(&self, default: &Branding) -> Branding {
+        Branding {
+            logo: self.logo.clone().or_else(|| default.logo.clone()),
+            logo_scale: self.logo_scale.or(default.logo_scale),
+            title: self.title.clone().or_else(|| default.title.clone()),
+            author: self.author.clone().or_else(|| default.author.clone()),
+            heading: self.heading.clone().or_else(|| default.heading.clone()),
+            disclaimer: self
+                .disclaimer
+                .clone()
+                .or_else(|| default.disclaimer.clone()),
+            footer: self.footer.clone().or_else(|| default.footer.clone()),
+        }
+    }
+
+    /// Return the line at the top of the pages: the logo, and the date on the
+    /// right
+    pub fn header(&self) -> String {
+        let logo = match self.logo.as_deref() {
+            Some(logo) if !logo.is_empty() => format!(
+                "\\includegraphics[scale={}]{{{}}}\n",
+                self.logo_scale.unwrap_or(DEFAULT_LOGO_SCALE),
+                logo
+            ),
+            _ => String::new(),
+        };
+        format!("{logo}\\hfill\\tiny Last Updated \\today")
+    }
+
+    /// Return the disclaimer and the footer, written small at the bottom of
+    /// the pages, or nothing when both are empty
+    pub fn bottom(&self) -> Option<String> {
+        let lines: Vec<&str> = [&self.disclaimer, &self.footer]
+            .into_iter()
+            .filter_map(|text| text.as_deref())
+            .filter(|text| !text.is_empty())
+            .collect();
+        match lines.is_empty() {
+            true => None,
+            false => Some(format!(
+                "{{\\scriptsize\n        {}}}",
+                lines.join("\\\\ \n ")
+            )),
+        }
+    }
+}
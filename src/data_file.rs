@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use crate::report::Report;
+use crate::GradeError;
+
+/// Read a report from a data file, in TOML for a `.toml` file and in JSON
+/// otherwise
+pub fn read_report(path: &str) -> Result<Report, GradeError> {
+    let data_error = |message: String| GradeError::DataFile {
+        path: path.to_string(),
+        message,
+    };
+    let content = std::fs::read_to_string(path).map_err(|e| data_error(e.to_string()))?;
+    let report: Report = match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| data_error(e.to_string()))?,
+        _ => serde_json::from_str(&content).map_err(|e| data_error(e.to_string()))?,
+    };
+    check_report(&report).map_err(data_error)?;
+    Ok(report)
+}
+
+/// Check that every category of a report can be written as a tabular: it has
+/// headers and rows, every row has a cell per header, and the parameters
+/// have a name.
+fn check_report(report: &Report) -> Result<(), String> {
+    for product in report.products.iter() {
+        for category in product.categories.iter() {
+            let name = format!(
+                "the category {:?} of {:?}",
+                category.title.text, product.name.text
+            );
+            if category.headers.is_empty() {
+                return Err(format!("{name} has no headers"));
+            }
+            if category.rows.is_empty() {
+                return Err(format!("{name} has no rows"));
+            }
+            if let Some(row) = category
+                .rows
+                .iter()
+                .find(|row| row.parameters.len() != category.headers.len())
+            {
+                return Err(format!(
+                    "{name} has {} headers, but a row has {} parameters",
+                    category.headers.len(),
+                    row.parameters.len()
+                ));
+            }
+            if category
+                .rows
+                .iter()
+                .all(|row| row.parameters[0].text.is_empty())
+            {
+                return Err(format!("{name} has no parameter names"));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use calamine::DataType;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde::Deserialize;
+
+use crate::GradeError;
+
+/// A SQLite database where the products are read: a query, or a table, with
+/// a line per value of a product
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseSource {
+    pub path: String,
+    /// Query returning the values, run instead of reading `table`
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub table: Option<String>,
+    #[serde(default)]
+    pub columns: DatabaseColumns,
+}
+
+/// Names of the columns of the query holding each part of a value
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DatabaseColumns {
+    pub product: String,
+    pub category: String,
+    pub parameter: String,
+    pub value: String,
+    /// Column of the unit of the parameters, when they have one
+    pub unit: Option<String>,
+    /// Column of the first level name of two-level parameters
+    pub group: Option<String>,
+}
+
+impl Default for DatabaseColumns {
+    fn default() -> Self {
+        Self {
+            product: String::from("product"),
+            category: String::from("category"),
+            parameter: String::from("parameter"),
+            value: String::from("value"),
+            unit: None,
+            group: None,
+        }
+    }
+}
+
+/// The result of the query: the names of its columns and its lines
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseTable {
+    pub columns: Vec<String>,
+    pub lines: Vec<Vec<DataType>>,
+}
+
+impl DatabaseTable {
+    /// Return the index of a column, failing when the query has no such column
+    pub fn column(&self, database: &DatabaseSource, name: &str) -> Result<usize, GradeError> {
+        self.columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or_else(|| GradeError::Database {
+                path: database.path.clone(),
+                message: format!(
+                    "no column {name:?} in {}, the columns are {:?}",
+                    database.name(),
+                    self.columns
+                ),
+            })
+    }
+}
+
+impl DatabaseSource {
+    /// Return the name of the read data, written in the addresses of its cells
+    pub fn name(&self) -> String {
+        match (&self.query, &self.table) {
+            (Some(_), _) => String::from("query"),
+            (None, Some(table)) => table.to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Run the query, or read the whole table, of the database. The database is
+    /// opened read only.
+    pub fn read(&self) -> Result<DatabaseTable, GradeError> {
+        let database_error = |message: String| GradeError::Database {
+            path: self.path.clone(),
+            message,
+        };
+        let query = match (&self.query, &self.table) {
+            (Some(query), _) => query.to_string(),
+            (None, Some(table)) => format!("SELECT * FROM \"{}\"", table.replace('"', "\"\"")),
+            (None, None) => return Err(database_error(String::from("no query nor table"))),
+        };
+        let connection = Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| database_error(e.to_string()))?;
+        let mut statement = connection
+            .prepare(&query)
+            .map_err(|e| database_error(e.to_string()))?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let mut rows = statement
+            .query([])
+            .map_err(|e| database_error(e.to_string()))?;
+        let mut lines: Vec<Vec<DataType>> = Vec::new();
+        while let Some(row) = rows.next().map_err(|e| database_error(e.to_string()))? {
+            let line = (0..columns.len())
+                .map(|i| row.get_ref(i).map(data_type))
+                .collect::<Result<Vec<DataType>, rusqlite::Error>>()
+                .map_err(|e| database_error(e.to_string()))?;
+            lines.push(line);
+        }
+        Ok(DatabaseTable { columns, lines })
+    }
+}
+
+/// Convert a SQLite value to a cell value
+fn data_type(value: ValueRef) -> DataType {
+    match value {
+        ValueRef::Null => DataType::Empty,
+        ValueRef::Integer(number) => DataType::Int(number),
+        ValueRef::Real(number) => DataType::Float(number),
+        ValueRef::Text(text) | ValueRef::Blob(text) => {
+            DataType::String(String::from_utf8_lossy(text).to_string())
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+
+use crate::sheet::CellAddress;
+use crate::summary::ErrorCell;
+use crate::SheetSource;
+
+/// Every error that can happen while reading the sources or creating a pdf.
+#[derive(Debug)]
+pub enum GradeError {
+    /// The configuration file cannot be read or parsed
+    Config {
+        path: String,
+        message: String,
+    },
+    /// The workbook cannot be opened
+    Workbook {
+        source: String,
+        message: String,
+    },
+    /// The JSON or TOML data file cannot be read or parsed
+    DataFile {
+        path: String,
+        message: String,
+    },
+    /// The SQLite database cannot be opened, or its query cannot be run
+    Database {
+        path: String,
+        message: String,
+    },
+    /// The worksheet is not in the workbook, with the closest sheet names
+    Worksheet {
+        source: String,
+        worksheet: String,
+        suggestions: Vec<String>,
+    },
+    /// Labels of the configuration are in none of the sheets
+    LabelNotFound {
+        labels: Vec<MissingLabel>,
+        sheets: Vec<SheetSource>,
+    },
+    /// A label of the configuration is found several times
+    DuplicateLabel {
+        label: String,
+        cells: Vec<CellAddress>,
+    },
+    /// The category of a label searched "within" it is in none of the sheets
+    ScopeNotFound {
+        label: String,
+        category: String,
+    },
+    /// The category of a label searched "within" it is found several times
+    AmbiguousScope {
+        category: String,
+        cells: Vec<CellAddress>,
+    },
+    /// The parameter overridden by a variant is in several categories
+    AmbiguousOverride {
+        variant: String,
+        parameter: String,
+        categories: Vec<String>,
+    },
+    /// A glob or regex label of the configuration is not valid
+    InvalidPattern {
+        pattern: String,
+        message: String,
+    },
+    /// A cell is read outside of its sheet
+    MissingCell(CellAddress),
+    /// A cell of a product page holds an Excel error, and the error policy is
+    /// to fail
+    ErrorCell(ErrorCell),
+    /// A pdf file reading sheets has no labels for products, categories or
+    /// parameters
+    EmptyLabels {
+        pdf_name: String,
+        field: String,
+    },
+    /// A category cannot be written as a table
+    Table {
+        title: String,
+        message: String,
+    },
+    /// The LaTeX template cannot be read or filled
+    Template {
+        path: String,
+        message: String,
+    },
+    /// The LaTeX document cannot be written or compiled
+    Latex {
+        pdf_name: String,
+        message: String,
+    },
+    Io(std::io::Error),
+}
+
+/// A label not found in the sheets, with the closest texts of the sheets and
+/// the cells where they are
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingLabel {
+    pub label: String,
+    pub suggestions: Vec<(String, Vec<CellAddress>)>,
+}
+
+impl fmt::Display for MissingLabel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.label)?;
+        for (i, (text, cells)) in self.suggestions.iter().enumerate() {
+            match i {
+                0 => write!(f, ", did you mean {text:?} (")?,
+                _ => write!(f, " or {text:?} (")?,
+            }
+            let cells: Vec<String> = cells.iter().map(|c| c.to_string()).collect();
+            write!(f, "{})", cells.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GradeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GradeError::Config { path, message } => {
+                write!(f, "cannot read the config file {path:?}: {message}")
+            }
+            GradeError::Workbook { source, message } => {
+                write!(f, "cannot open the workbook {source:?}: {message}")
+            }
+            GradeError::DataFile { path, message } => {
+                write!(f, "cannot read the data file {path:?}: {message}")
+            }
+            GradeError::Database { path, message } => {
+                write!(f, "cannot read the database {path:?}: {message}")
+            }
+            GradeError::Worksheet {
+                source,
+                worksheet,
+                suggestions,
+            } => {
+                write!(f, "no worksheet {worksheet:?} in {source:?}")?;
+                for (i, name) in suggestions.iter().enumerate() {
+                    match i {
+                        0 => write!(f, ", did you mean {name:?}")?,
+                        _ => write!(f, " or {name:?}")?,
+                    }
+                }
+                Ok(())
+            }
+            GradeError::LabelNotFound { labels, sheets } => {
+                write!(f, "labels not found in")?;
+                for sheet in sheets {
+                    match sheet.worksheet.is_empty() {
+                        true => write!(f, " {:?}", sheet.source)?,
+                        false => write!(f, " {:?}[{:?}]", sheet.source, sheet.worksheet)?,
+                    }
+                }
+                for label in labels {
+                    write!(f, "\n    {label}")?;
+                }
+                Ok(())
+            }
+            GradeError::DuplicateLabel { label, cells } => {
+                write!(f, "label {label:?} found several times:")?;
+                for cell in cells {
+                    write!(f, " {cell}")?;
+                }
+                write!(f, ", use \"within\" or \"range\" to keep only one")
+            }
+            GradeError::ScopeNotFound { label, category } => {
+                write!(
+                    f,
+                    "the category {category:?} of the label {label:?} is not found"
+                )
+            }
+            GradeError::AmbiguousScope { category, cells } => {
+                write!(
+                    f,
+                    "the category {category:?} of a \"within\" label is found several times:"
+                )?;
+                for cell in cells {
+                    write!(f, " {cell}")?;
+                }
+                Ok(())
+            }
+            GradeError::AmbiguousOverride {
+                variant,
+                parameter,
+                categories,
+            } => {
+                write!(
+                    f,
+                    "the parameter {parameter:?} overridden by the variant {variant:?} is in several categories: {categories:?}"
+                )
+            }
+            GradeError::InvalidPattern { pattern, message } => {
+                write!(f, "invalid label pattern {pattern:?}: {message}")
+            }
+            GradeError::MissingCell(cell) => write!(f, "cell {cell} is outside of the sheet"),
+            GradeError::ErrorCell(cell) => write!(f, "error cell {cell}"),
+            GradeError::EmptyLabels { pdf_name, field } => {
+                write!(
+                    f,
+                    "the pdf file {pdf_name:?} reads sheets but has no {field}"
+                )
+            }
+            GradeError::Table { title, message } => {
+                write!(f, "cannot write the table {title:?}: {message}")
+            }
+            GradeError::Template { path, message } => {
+                write!(f, "cannot fill the template {path:?}: {message}")
+            }
+            GradeError::Latex { pdf_name, message } => {
+                write!(f, "cannot create the pdf {pdf_name:?}: {message}")
+            }
+            GradeError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for GradeError {}
+
+impl From<std::io::Error> for GradeError {
+    fn from(e: std::io::Error) -> Self {
+        GradeError::Io(e)
+    }
+}
@@ -0,0 +1,23 @@
+/// Escape the text of a cell to be written as it is in a LaTeX document: the
+/// special characters of LaTeX and the signs only written in math mode. The
+/// units are typeset apart, see `typeset_unit`.
+pub fn escape_latex(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => output.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                output.push('\\');
+                output.push(c);
+            }
+            '~' => output.push_str("\\textasciitilde{}"),
+            '^' => output.push_str("\\textasciicircum{}"),
+            // spaced like "< 0,15" even when written "<0,15"
+            '<' => output.push_str("\\(<\\) "),
+            '>' => output.push_str("\\(>\\) "),
+            'µ' | 'μ' => output.push_str("\\(\\mu\\)"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
@@ -0,0 +1,74 @@
+use std::fs::File;
+use std::path::Path;
+
+use crate::report::Report;
+use crate::sheet::cell_name;
+use crate::GradeError;
+
+/// Columns of the csv export, a line per value of a product
+const CSV_HEADER: [&str; 6] = [
+    "product",
+    "category",
+    "parameter",
+    "value",
+    "unit",
+    "source cell",
+];
+
+/// Write the report as JSON, with the typed values and their cells
+pub fn write_json(report: &Report, path: &Path) -> Result<(), GradeError> {
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, report).map_err(std::io::Error::from)?;
+    Ok(())
+}
+
+/// Write the report as a flat csv: a line per value of a product, with its
+/// category, its parameter, its unit and the cell it was read from, like
+/// `'[products.xlsx]Overview'!C5`. The empty values, left out of the pdf,
+/// are left out too.
+pub fn write_csv(report: &Report, path: &Path) -> Result<(), GradeError> {
+    let mut writer = csv::Writer::from_path(path).map_err(std::io::Error::from)?;
+    writer
+        .write_record(CSV_HEADER)
+        .map_err(std::io::Error::from)?;
+    for product in report.products.iter() {
+        for category in product.categories.iter() {
+            let unit_index = category.unit_index();
+            for row in category
+                .rows
+                .iter()
+                .filter(|row| !row.value.text.is_empty())
+            {
+                let parameter = row.name();
+                let unit = unit_index
+                    .and_then(|i| row.parameters.get(i))
+                    .map_or("", |cell| cell.text.as_str());
+                let source_cell = row
+                    .value
+                    .address
+                    .as_ref()
+                    .map(|address| {
+                        format!(
+                            "'[{}]{}'!{}",
+                            address.source,
+                            address.worksheet,
+                            cell_name(address.cell)
+                        )
+                    })
+                    .unwrap_or_default();
+                writer
+                    .write_record([
+                        product.name.text.as_str(),
+                        category.title.text.as_str(),
+                        &parameter,
+                        &row.value.text,
+                        unit,
+                        &source_cell,
+                    ])
+                    .map_err(std::io::Error::from)?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
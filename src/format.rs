@@ -0,0 +1,162 @@
+use calamine::DataType;
+use serde::Deserialize;
+
+/// Significant figures of the numbers written without a format, as Excel
+/// shows them
+const DEFAULT_SIGNIFICANT_FIGURES: usize = 15;
+
+/// Date format used when none is given
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Days between the first day of Excel dates (1899-12-30) and 1970-01-01
+const EXCEL_UNIX_EPOCH: i64 = 25569;
+
+/// How the values of a cell are written in the pdf.
+/// Every option not set is taken from the default format of the pdf file.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NumberFormat {
+    /// Number of digits after the decimal point
+    #[serde(default)]
+    pub decimals: Option<usize>,
+    /// Number of significant figures, when `decimals` is not set
+    #[serde(default)]
+    pub significant_figures: Option<usize>,
+    /// Separator between groups of thousands, like "," or " "
+    #[serde(default)]
+    pub thousands_separator: Option<String>,
+    /// Format of the dates with %Y, %y, %m, %d, %H, %M and %S, "%Y-%m-%d" by
+    /// default
+    #[serde(default)]
+    pub date_format: Option<String>,
+}
+
+impl NumberFormat {
+    /// Return this format completed with the options of `default`
+    pub fn or(&self, default: &NumberFormat) -> NumberFormat {
+        NumberFormat {
+            decimals: self.decimals.or(default.decimals),
+            significant_figures: self.significant_figures.or(default.significant_figures),
+            thousands_separator: self
+                .thousands_separator
+                .clone()
+                .or_else(|| default.thousands_separator.clone()),
+            date_format: self
+                .date_format
+                .clone()
+                .or_else(|| default.date_format.clone()),
+        }
+    }
+
+    /// Write the value of a cell
+    pub fn format(&self, value: &DataType) -> String {
+        match value {
+            DataType::Float(number) => self.format_number(*number),
+            DataType::Int(number) => self.format_number(*number as f64),
+            DataType::DateTime(serial) => self.format_date(*serial),
+            DataType::String(text) => text.to_string(),
+            DataType::Bool(_) | DataType::Error(_) | DataType::Empty => value.to_string(),
+        }
+    }
+
+    fn format_number(&self, number: f64) -> String {
+        let text = match (self.decimals, self.significant_figures) {
+            (Some(decimals), _) => format!("{number:.decimals$}"),
+            (None, Some(figures)) => round_significant(number, figures),
+            (None, None) => trim_zeros(round_significant(number, DEFAULT_SIGNIFICANT_FIGURES)),
+        };
+        match &self.thousands_separator {
+            Some(separator) => group_thousands(&text, separator),
+            None => text,
+        }
+    }
+
+    fn format_date(&self, serial: f64) -> String {
+        let days = serial.floor() as i64;
+        let seconds = ((serial - serial.floor()) * 86400.0).round() as i64;
+        let (year, month, day) = civil_from_days(days - EXCEL_UNIX_EPOCH);
+        let format = self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+        let mut output = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                output.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => output.push_str(&format!("{year:04}")),
+                Some('y') => output.push_str(&format!("{:02}", year.rem_euclid(100))),
+                Some('m') => output.push_str(&format!("{month:02}")),
+                Some('d') => output.push_str(&format!("{day:02}")),
+                Some('H') => output.push_str(&format!("{:02}", seconds / 3600)),
+                Some('M') => output.push_str(&format!("{:02}", seconds / 60 % 60)),
+                Some('S') => output.push_str(&format!("{:02}", seconds % 60)),
+                Some(other) => {
+                    output.push('%');
+                    output.push(other);
+                }
+                None => output.push('%'),
+            }
+        }
+        output
+    }
+}
+
+/// Write a number rounded to `figures` significant figures
+fn round_significant(number: f64, figures: usize) -> String {
+    if number == 0.0 || !number.is_finite() {
+        return number.to_string();
+    }
+    let magnitude = number.abs().log10().floor() as i32;
+    let decimals = figures.max(1) as i32 - 1 - magnitude;
+    if decimals >= 0 {
+        format!("{number:.0$}", decimals as usize)
+    } else {
+        let scale = 10f64.powi(-decimals);
+        format!("{:.0}", (number / scale).round() * scale)
+    }
+}
+
+/// Remove the zeros at the end of the decimals, and the point if nothing is
+/// left after it
+fn trim_zeros(text: String) -> String {
+    match text.contains('.') {
+        true => text.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => text,
+    }
+}
+
+/// Insert a separator between the groups of thousands of the integer part
+fn group_thousands(text: &str, separator: &str) -> String {
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", text),
+    };
+    let (integer, decimals) = match unsigned.find('.') {
+        Some(point) => unsigned.split_at(point),
+        None => (unsigned, ""),
+    };
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    format!("{sign}{grouped}{decimals}")
+}
+
+/// Return the (year, month, day) of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,43 @@
+use latex::Document;
+
+use crate::branding::Branding;
+use crate::error::GradeError;
+use crate::report::{Report, ReportCategory, ReportProduct};
+
+/// How the pages of a document are composed from a report. The BIOTEC layout
+/// of `ConfigXlsx` is the default one, another layout can reuse its parts.
+pub trait PageLayout {
+    /// Add the packages and the definitions of the document
+    fn preamble(&self, page: &mut Document, branding: &Branding);
+
+    /// Add the first page, before the pages of the products
+    fn cover_page(&self, page: &mut Document, report: &Report, branding: &Branding);
+
+    /// Add the page of a product
+    fn product_page(
+        &self,
+        page: &mut Document,
+        product: &ReportProduct,
+        branding: &Branding,
+    ) -> Result<(), GradeError>;
+
+    /// Add the table of a category of a product, failing when the category
+    /// cannot be written as a table
+    fn category_table(
+        &self,
+        page: &mut Document,
+        category: &ReportCategory,
+    ) -> Result<(), GradeError>;
+
+    /// Create the document of a report: the preamble, the cover page, then a
+    /// page per product
+    fn compose(&self, report: &Report, branding: &Branding) -> Result<Document, GradeError> {
+        let mut page = Document::new(latex::DocumentClass::Article);
+        self.preamble(&mut page, branding);
+        self.cover_page(&mut page, report, branding);
+        for product in report.products.iter() {
+            self.product_page(&mut page, product, branding)?;
+        }
+        Ok(page)
+    }
+}
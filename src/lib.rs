@@ -1,17 +1,48 @@
+use calamine::DataType;
 use latex::{print, Document, Element, PreambleElement};
+use std::collections::HashMap;
 use std::default::Default;
-use std::error::Error;
 use std::fs::File;
-use std::io::{BufReader, Write};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use strum_macros::EnumIter;
 
-use calamine::{open_workbook, DataType, Reader, Xlsx};
-
 use serde::Deserialize;
 
+mod branding;
+mod data_file;
+mod database;
+mod error;
+mod escape;
+mod export;
+mod format;
+mod layout;
+mod matching;
+mod merged_cells;
+mod report;
+mod sheet;
+mod suggest;
+mod summary;
 mod tab_creation;
+mod template;
+mod units;
+
+pub use branding::Branding;
+pub use database::{DatabaseColumns, DatabaseSource, DatabaseTable};
+pub use error::{GradeError, MissingLabel};
+pub use escape::escape_latex;
+pub use format::NumberFormat;
+pub use layout::PageLayout;
+pub use matching::{Label, LabelMatcher, LabelMatching, Region};
+pub use report::{
+    CellValue, Report, ReportCategory, ReportCell, ReportField, ReportProduct, ReportRow,
+};
+pub use sheet::{cell_name, CellAddress, Sheet, SheetCache};
+pub use summary::{ErrorCell, RunSummary};
+pub use template::{render_template, TemplateScope, TemplateValue};
+pub use units::{typeset_unit, typeset_value};
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -20,9 +51,18 @@ pub struct ConfigXlsx {
     pub color_text: Vec<i32>,
     pub color_tab_title: Vec<i32>,
     pub color_tab_line: Vec<i32>,
+    /// Background of the highlighted values
+    #[serde(default = "default_color_highlight")]
+    pub color_highlight: Vec<i32>,
     assets: String,
     pub margin_size: f32,
     pub alignment_tabular: String,
+    /// Logo and texts of the documents, BIOTEC by default
+    #[serde(default)]
+    pub branding: Branding,
+    /// LaTeX template of the documents, used instead of the default layout
+    #[serde(default)]
+    pub template: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -30,11 +70,137 @@ pub struct ConfigXlsx {
 pub struct PdfFile {
     pdf_name: String,
     output: String,
+    #[serde(default)]
     source: String,
+    #[serde(default)]
     worksheet: String,
-    products: Vec<String>,
-    categories: Vec<String>,
-    parameters: Vec<String>,
+    #[serde(default)]
+    sheets: Vec<SheetSource>,
+    /// JSON or TOML file with the products, read instead of the sheets
+    #[serde(default)]
+    data: String,
+    /// SQLite database with the products, read instead of the sheets
+    #[serde(default)]
+    database: Option<DatabaseSource>,
+    #[serde(default)]
+    matching: LabelMatching,
+    /// Whether the products are the rows or the columns of the sheets
+    #[serde(default)]
+    orientation: Orientation,
+    /// Format of every value, unless its column has its own format
+    #[serde(default)]
+    number_format: NumberFormat,
+    /// Formats of the columns, by the name of their parameter or their unit
+    #[serde(default)]
+    formats: HashMap<String, NumberFormat>,
+    /// What to do with the cells holding an Excel error
+    #[serde(default)]
+    error_cells: ErrorPolicy,
+    /// Text written instead of an error with the placeholder policy
+    #[serde(default = "default_error_placeholder")]
+    error_placeholder: String,
+    #[serde(default)]
+    products: Vec<Label>,
+    #[serde(default)]
+    categories: Vec<Label>,
+    #[serde(default)]
+    parameters: Vec<Label>,
+    /// Label of the row of the second level of the parameter names, under the
+    /// row of the first parameter
+    #[serde(default)]
+    sub_parameters: Option<Label>,
+    /// Worksheet with more fields of the products, joined by their name
+    #[serde(default)]
+    lookup: Option<LookupSheet>,
+    /// Products made from another product with a few values changed
+    #[serde(default)]
+    variants: Vec<Variant>,
+    /// Branding of the pdf file, completed by the branding of the config
+    #[serde(default)]
+    branding: Option<Branding>,
+    /// LaTeX template of the pdf file, instead of the template of the config
+    #[serde(default)]
+    template: Option<String>,
+}
+
+/// A worksheet of a workbook where products and categories are read.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetSource {
+    pub source: String,
+    #[serde(default)]
+    pub worksheet: String,
+}
+
+/// A worksheet with a line per product, like its description or its
+/// certification number, under a line of headers
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupSheet {
+    pub source: String,
+    #[serde(default)]
+    pub worksheet: String,
+    /// Label of the header of the column of the product names
+    pub key: Label,
+    /// Headers of the columns joined to the products, every column when empty
+    #[serde(default)]
+    pub fields: Vec<String>,
+}
+
+/// A product with the values of a base product, but a few of them. The changed
+/// values are read from the row of the variant in the sheets, when it has one,
+/// and from the overrides.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Variant {
+    pub name: String,
+    /// Name of the base product, which must be one of the products
+    pub base: String,
+    /// Values by parameter name, like "Density" or "Tensile strength MD" for
+    /// a grouped parameter
+    #[serde(default)]
+    pub overrides: HashMap<String, ReportCell>,
+    /// Whether the changed values are highlighted in the tables
+    #[serde(default)]
+    pub highlight: bool,
+}
+
+/// The group of a parameter column and the second level name of the column,
+/// as (group, name)
+pub type ParameterGroup = (String, String);
+
+/// Layout of the sheets of a pdf file
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Orientation {
+    /// A product per row, the parameters in columns
+    #[default]
+    Rows,
+    /// A product per column, the parameters in rows
+    Columns,
+}
+
+/// What to do with the cells holding an Excel error, like `#N/A`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorPolicy {
+    /// Write the error placeholder instead
+    Placeholder,
+    /// Leave the cell blank
+    Blank,
+    /// Stop the creation of the pdf
+    Fail,
+    /// Write the error as it is, with a warning giving the cell address
+    #[default]
+    Warn,
+}
+
+fn default_error_placeholder() -> String {
+    String::from("n/a")
+}
+
+fn default_color_highlight() -> Vec<i32> {
+    Vec::from([255, 236, 179])
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -54,29 +220,51 @@ pub enum TabParameters {
 #[derive(Debug, Clone)]
 pub struct Config {
     config_path: String,
+    export: bool,
 }
 
 impl Config {
+    /// Read the command line: the path of the config file, and `--export` to
+    /// write the extracted data instead of the pdf files
     pub fn new(args: &[String]) -> Result<Config, &'static str> {
         println!("{:?}", args);
-        if args.len() < 2 {
-            return Err("Not enough argument");
+        let config_path = args.iter().skip(1).find(|arg| *arg != "--export");
+        match config_path {
+            Some(config_path) => Ok(Config {
+                config_path: config_path.clone(),
+                export: Self::has_export_flag(args),
+            }),
+            None => Err("Not enough argument"),
         }
+    }
 
-        Ok(Config {
-            config_path: args[1].clone(),
-        })
+    /// Return the default config, keeping the `--export` flag of the command
+    /// line, when no config file is given
+    pub fn default_from(args: &[String]) -> Config {
+        Config {
+            export: Self::has_export_flag(args),
+            ..Config::default()
+        }
+    }
+
+    fn has_export_flag(args: &[String]) -> bool {
+        args.iter().skip(1).any(|arg| arg == "--export")
     }
 
     pub fn get_config_path(&self) -> &String {
         &self.config_path
     }
+
+    pub fn is_export(&self) -> bool {
+        self.export
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             config_path: String::from("config/config_source.json"),
+            export: false,
         }
     }
 }
@@ -85,7 +273,7 @@ impl Default for Config {
 /// Seperate function to handle the windows server lately -> ToDo
 ///
 pub fn render_tex_file(rendered: String, pdf_name: String, output: String) -> std::io::Result<()> {
-    let mut f = File::create(&format!("{}/{}.tex", output, pdf_name))?;
+    let mut f = File::create(format!("{}/{}.tex", output, pdf_name))?;
 
     write!(f, "{}", rendered)?;
     Ok(())
@@ -98,9 +286,12 @@ impl Default for ConfigXlsx {
             color_text: Vec::from([13, 64, 47]),
             color_tab_title: Vec::from([237, 233, 230]),
             color_tab_line: Vec::from([215, 212, 210]),
+            color_highlight: default_color_highlight(),
             assets: String::from("resources/"),
             margin_size: 0.80,
             alignment_tabular: String::from("left"),
+            branding: Branding::default(),
+            template: None,
         }
     }
 }
@@ -113,17 +304,24 @@ impl ConfigXlsx {
             color_text: Vec::new(),
             color_tab_title: Vec::new(),
             color_tab_line: Vec::new(),
+            color_highlight: default_color_highlight(),
             assets: String::new(),
             margin_size: 0.84,
             alignment_tabular: String::from("left"),
+            branding: Branding::default(),
+            template: None,
         }
     }
 
     /// from a path
-    pub fn from(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let path = Path::new(path);
-        let file = File::open(path)?;
-        let config: ConfigXlsx = serde_json::from_reader(file)?;
+    pub fn from(path: &str) -> Result<Self, GradeError> {
+        let config_error = |message: String| GradeError::Config {
+            path: path.to_string(),
+            message,
+        };
+        let file = File::open(Path::new(path)).map_err(|e| config_error(e.to_string()))?;
+        let config: ConfigXlsx =
+            serde_json::from_reader(file).map_err(|e| config_error(e.to_string()))?;
         Ok(config)
     }
 
@@ -135,16 +333,203 @@ impl ConfigXlsx {
             && self.color_text.is_empty()
             && self.pdf_file.is_empty()
     }
+
+    /// Create the document of a report with the branding of the config
+    pub fn render(&self, report: &Report) -> Result<Document, GradeError> {
+        self.render_with_branding(report, &self.branding.or(&Branding::default()))
+    }
+
+    /// Return the branding of a pdf file: its own options, completed with the
+    /// ones of the config
+    pub fn get_branding(&self, pdf_file: &PdfFile) -> Branding {
+        let branding = self.branding.or(&Branding::default());
+        match &pdf_file.branding {
+            Some(own) => own.or(&branding),
+            None => branding,
+        }
+    }
+
+    /// Return the template of a pdf file, or else of the config
+    pub fn get_template<'a>(&'a self, pdf_file: &'a PdfFile) -> Option<&'a String> {
+        pdf_file.template.as_ref().or(self.template.as_ref())
+    }
+
+    /// Write the LaTeX of a report, from the template of the pdf file when
+    /// there is one, or else with the default layout
+    pub fn render_tex(&self, report: &Report, pdf_file: &PdfFile) -> Result<String, GradeError> {
+        let branding = self.get_branding(pdf_file);
+        match self.get_template(pdf_file) {
+            Some(path) => {
+                let template = std::fs::read_to_string(path).map_err(|e| GradeError::Template {
+                    path: path.to_string(),
+                    message: e.to_string(),
+                })?;
+                render_template(&template, &self.template_scope(report, &branding)?).map_err(
+                    |message| GradeError::Template {
+                        path: path.to_string(),
+                        message,
+                    },
+                )
+            }
+            None => print(&self.render_with_branding(report, &branding)?).map_err(|e| {
+                GradeError::Latex {
+                    pdf_name: report.pdf_name.clone(),
+                    message: e.to_string(),
+                }
+            }),
+        }
+    }
+
+    /// Return the values given to a template: the preamble and the branding,
+    /// and the products with their fields and categories, their texts
+    /// escaped. A category has its
+    /// default tabular as `table`, and its rows for the tables written in the
+    /// template. The fields of a product and the parameters of a row are also
+    /// named by their header.
+    pub fn template_scope(
+        &self,
+        report: &Report,
+        branding: &Branding,
+    ) -> Result<TemplateScope, GradeError> {
+        let latex_error = |message: String| GradeError::Latex {
+            pdf_name: report.pdf_name.clone(),
+            message,
+        };
+        let text = |text: &str| TemplateValue::Text(text.to_string());
+        let option = |value: &Option<String>| text(value.as_deref().unwrap_or_default());
+        // the texts of the cells are escaped, the branding is already LaTeX
+        let cell_text = |value: &str| TemplateValue::Text(escape_latex(value));
+
+        // the preamble is everything printed before the document content
+        let mut document = Document::new(latex::DocumentClass::Article);
+        self.preamble(&mut document, branding);
+        let preamble = print(&document).map_err(|e| latex_error(e.to_string()))?;
+        let preamble = match preamble.find("\\begin{document}") {
+            Some(end) => preamble[..end].to_string(),
+            None => preamble,
+        };
+
+        let mut products: Vec<TemplateScope> = Vec::new();
+        for (i, product) in report.products.iter().enumerate() {
+            let mut categories: Vec<TemplateScope> = Vec::new();
+            for category in product.categories.iter() {
+                let mut table = Document::new(latex::DocumentClass::Part);
+                self.category_table(&mut table, category)?;
+                let unit_index = category.unit_index();
+                let rows = category
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let (parameter_texts, value) = units::typeset_row(row, unit_index);
+                        let mut scope: TemplateScope = category
+                            .headers
+                            .iter()
+                            .zip(parameter_texts.iter())
+                            .map(|(header, parameter)| {
+                                (header.text.trim().to_string(), text(parameter))
+                            })
+                            .collect();
+                        let parameters = parameter_texts
+                            .iter()
+                            .map(|parameter| {
+                                TemplateScope::from([(String::from("text"), text(parameter))])
+                            })
+                            .collect();
+                        scope.insert(String::from("parameters"), TemplateValue::List(parameters));
+                        scope.insert(String::from("name"), cell_text(&row.name()));
+                        scope.insert(
+                            String::from("group"),
+                            cell_text(row.group.as_deref().unwrap_or_default()),
+                        );
+                        scope.insert(String::from("value"), text(&value));
+                        let highlighted = if row.highlighted { "true" } else { "" };
+                        scope.insert(String::from("highlighted"), text(highlighted));
+                        scope
+                    })
+                    .collect();
+                let headers = category
+                    .headers
+                    .iter()
+                    .map(|header| {
+                        TemplateScope::from([(String::from("text"), cell_text(&header.text))])
+                    })
+                    .collect();
+                categories.push(TemplateScope::from([
+                    (String::from("title"), cell_text(&category.title.text)),
+                    (
+                        String::from("table"),
+                        text(&print(&table).map_err(|e| latex_error(e.to_string()))?),
+                    ),
+                    (String::from("headers"), TemplateValue::List(headers)),
+                    (String::from("rows"), TemplateValue::List(rows)),
+                ]));
+            }
+            let mut scope: TemplateScope = product
+                .fields
+                .iter()
+                .map(|field| (field.name.trim().to_string(), cell_text(&field.value.text)))
+                .collect();
+            let fields = product
+                .fields
+                .iter()
+                .map(|field| {
+                    TemplateScope::from([
+                        (String::from("name"), cell_text(&field.name)),
+                        (String::from("value"), cell_text(&field.value.text)),
+                    ])
+                })
+                .collect();
+            scope.insert(String::from("fields"), TemplateValue::List(fields));
+            scope.insert(String::from("name"), cell_text(&product.name.text));
+            scope.insert(String::from("index"), text(&(i + 1).to_string()));
+            // the first page is the cover
+            scope.insert(String::from("page"), text(&(i + 2).to_string()));
+            scope.insert(String::from("categories"), TemplateValue::List(categories));
+            products.push(scope);
+        }
+
+        Ok(TemplateScope::from([
+            (String::from("preamble"), text(&preamble)),
+            (String::from("pdfName"), cell_text(&report.pdf_name)),
+            (String::from("header"), text(&branding.header())),
+            (String::from("logo"), option(&branding.logo)),
+            (String::from("title"), option(&branding.title)),
+            (String::from("author"), option(&branding.author)),
+            (String::from("heading"), option(&branding.heading)),
+            (String::from("disclaimer"), option(&branding.disclaimer)),
+            (String::from("footer"), option(&branding.footer)),
+            (
+                String::from("bottom"),
+                text(&branding.bottom().unwrap_or_default()),
+            ),
+            (String::from("products"), TemplateValue::List(products)),
+        ]))
+    }
+
+    /// Create the document of a report with the default layout
+    pub fn render_with_branding(
+        &self,
+        report: &Report,
+        branding: &Branding,
+    ) -> Result<Document, GradeError> {
+        self.compose(report, branding)
+    }
+}
+
+/// The BIOTEC layout: a cover page with the list of the products, then a page
+/// per product with a tabularx per category
+impl PageLayout for ConfigXlsx {
     /// To define all the preamble element of the page.
     /// All the key element are in the config file
     ///
-    pub fn preamble(&self, page: &mut Document) {
+    fn preamble(&self, page: &mut Document, branding: &Branding) {
         page.preamble.use_package("tabularx");
         page.preamble.use_package("xcolor");
         page.preamble.use_package("colortbl");
         page.preamble.use_package("geometry");
         page.preamble.use_package("paracol");
         page.preamble.use_package("graphicx");
+        page.preamble.use_package("siunitx");
         let margin: PreambleElement = PreambleElement::UserDefined(String::from(&format!(
             "\\geometry{{margin={}in}}",
             self.margin_size
@@ -192,13 +577,24 @@ impl ConfigXlsx {
                 })
                 .collect::<String>()
         )));
-        page.preamble.author("Biotec");
-        page.preamble.title("Template");
+        let def_color_highlight = PreambleElement::UserDefined(format!(
+            "\\definecolor{{highlight_color}}{{RGB}}{{{}}}",
+            self.color_highlight
+                .iter()
+                .map(|val| val.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        ));
+        page.preamble
+            .author(branding.author.as_deref().unwrap_or_default());
+        page.preamble
+            .title(branding.title.as_deref().unwrap_or_default());
         page.preamble
             .push(margin)
             .push(def_color_title)
             .push(def_color_font)
-            .push(def_color_line);
+            .push(def_color_line)
+            .push(def_color_highlight);
 
         page.preamble
             .push(PreambleElement::UserDefined(String::from(
@@ -217,10 +613,13 @@ impl ConfigXlsx {
                 "\\renewcommand{\\arraystretch}{1.25}",
             )));
         page.preamble
-            .push(PreambleElement::UserDefined(String::from(format!(
-                "\\graphicspath{{{{../{}/}}}}",
-                self.assets
-            ))));
+            .push(PreambleElement::UserDefined(String::from(
+                "\\sisetup{per-mode=symbol}",
+            )));
+        page.preamble.push(PreambleElement::UserDefined(format!(
+            "\\graphicspath{{{{../{}/}}}}",
+            self.assets
+        )));
         page.preamble
             .push(PreambleElement::UserDefined(String::from(
                 "\\newcommand\\setItemnumber[1]{\\setcounter{enumi}{\\numexpr#1-1\\relax}}",
@@ -229,12 +628,17 @@ impl ConfigXlsx {
 
     /// Define the first page of the document
     /// We find on it only the names of the products
-    pub fn first_page(&self, page: &mut Document, product_names: &Option<Vec<String>>) {
-        let image = String::from(
-            "\\includegraphics[scale=0.20]{biotec}\n\\hfill\\tiny Last Updated \\today",
+    fn cover_page(&self, page: &mut Document, report: &Report, branding: &Branding) {
+        let product_names = report.product_names();
+        let product_names = match product_names.is_empty() {
+            true => None,
+            false => Some(product_names),
+        };
+        let image = tab_creation::define_environment(
+            "flushleft".to_string(),
+            "".to_string(),
+            branding.header(),
         );
-        let image =
-            tab_creation::define_environment("flushleft".to_string(), "".to_string(), image);
 
         let mut table_of_content =
             String::from("\\hspace{1cm}\\\\\n\\textbf{Contents}\\\\\n\\hspace{5in}\\\\\n");
@@ -243,7 +647,7 @@ impl ConfigXlsx {
             Some(products) => {
                 for (i, product_name) in products.iter().enumerate() {
                     item_product.push(format!("\\setItemnumber{{{}}}\n", i + 2));
-                    item_product.push(format!("\\item {}\\\\\n", product_name))
+                    item_product.push(format!("\\item {}\\\\\n", escape_latex(product_name)))
                 }
             }
             None => item_product.push("\\item No product Given\\\\\n".to_string()),
@@ -263,74 +667,116 @@ impl ConfigXlsx {
             table_of_content,
         )));
         page.push(Element::UserDefined(String::from("\\vspace*{\\fill}")));
-        page.push(Element::UserDefined(String::from("{\\scriptsize
-        \\textbf{Disclaimer} This information and our technical advice - whether verbal, in writing or by way of trials - are given in good faith but without warranty, and this also applies where proprietary rights of third parties are involved. Our advice does not release you from the obligation to check its validity and to test our products as to their suitability for the intended processes and uses. The application, use and processing of our products and the products manufactured by you on the basis of our technical advice are beyond our control and, therefore, entirely your own responsibility. Our products are sold in accordance with our General Conditions of Sale and Delivery.\\\\ \n BIOTEC Biologische Naturverpackungen GmbH \\& Co. KG · Werner-Heisenberg-Str. 32 · D.46446 Emmerich \\hfill \\textbf{T} +49 2822 92510\\qquad \\textbf{W} biotec.de}")));
+        if let Some(bottom) = branding.bottom() {
+            page.push(Element::UserDefined(bottom));
+        }
         page.push(Element::ClearPage);
     }
 
-    /// Create the string that will be compiled.
-    /// This function will be depending on json files later. -> Todo
-    ///
-    pub fn page_blue_print(
+    /// Add the tabular of a category to the page
+    fn category_table(
         &self,
         page: &mut Document,
-        product_name: String,
-        titles: &Option<Vec<String>>,
-        parameters: &Option<Vec<String>>,
-        general_contents: &Option<Vec<Vec<String>>>,
-        product_contents: &Vec<Vec<String>>,
-        nb_param: usize,
-    ) -> Option<()> {
-        // we iterate over tabulars
-        let mut general_content = general_contents.as_ref()?.iter();
-        let mut title = titles.as_ref()?.iter();
-        let mut product_content = product_contents.iter();
-        let image = String::from(
-            "\\includegraphics[scale=0.20]{biotec}\n\\hfill\\tiny Last Updated \\today",
+        category: &ReportCategory,
+    ) -> Result<(), GradeError> {
+        let align = match self.alignment_tabular.as_str() {
+            "left" => AlignTab::L,
+            "right" => AlignTab::R,
+            "center" => AlignTab::C,
+            _ => AlignTab::L,
+        };
+        // the texts of the cells are escaped before being put in the tabular
+        let mut params: Vec<String> = category
+            .headers
+            .iter()
+            .map(|header| escape_latex(&header.text))
+            .collect();
+        // the units and the quantities are typeset with siunitx
+        let unit_index = category.unit_index();
+        let (parameters, product_content): (Vec<Vec<String>>, Vec<String>) = category
+            .rows
+            .iter()
+            .map(|row| {
+                let (parameters, value) = units::typeset_row(row, unit_index);
+                match row.highlighted && !row.value.text.is_empty() {
+                    true => (parameters, format!("\\cellcolor{{highlight_color}}{value}")),
+                    false => (parameters, value),
+                }
+            })
+            .unzip();
+        let parameters: Vec<String> = parameters.into_iter().flatten().collect();
+        let groups: Vec<Option<ParameterGroup>> = category
+            .rows
+            .iter()
+            .map(|row| {
+                let name = row.parameters.first()?;
+                Some((escape_latex(row.group.as_ref()?), escape_latex(&name.text)))
+            })
+            .collect();
+        tab_creation::create_tabularx(
+            page,
+            &escape_latex(&category.title.text),
+            &mut params,
+            &parameters,
+            &product_content,
+            category.headers.len(),
+            &groups,
+            &align,
+        )
+        .map_err(|message| GradeError::Table {
+            title: category.title.text.clone(),
+            message,
+        })
+    }
+
+    /// Create the page of a product, with a tabular per category.
+    fn product_page(
+        &self,
+        page: &mut Document,
+        product: &ReportProduct,
+        branding: &Branding,
+    ) -> Result<(), GradeError> {
+        let image = tab_creation::define_environment(
+            "flushleft".to_string(),
+            "".to_string(),
+            branding.header(),
         );
-        let image =
-            tab_creation::define_environment("flushleft".to_string(), "".to_string(), image);
         page.push(Element::UserDefined(image));
 
+        // the fields of the lookup sheet are written under the product name
+        let fields: String = product
+            .fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}: {}\\\\\n",
+                    escape_latex(&field.name),
+                    escape_latex(&field.value.text)
+                )
+            })
+            .collect();
         let intro = String::from(&format!(
-        "\\hspace{{1cm}}\\\\\n\\textbf{{Preliminary Data Sheed}}\\\\\n{}\\\\\n\\hspace{{1cm}}\\\\",
-        product_name
+            "\\hspace{{1cm}}\\\\\n\\textbf{{{}}}\\\\\n{}\\\\\n{}\\hspace{{1cm}}\\\\",
+            branding.heading.as_deref().unwrap_or_default(),
+            escape_latex(&product.name.text),
+            fields
         ));
         page.push(Element::UserDefined(tab_creation::define_environment(
             "flushleft".to_string(),
             "".to_string(),
             intro,
         )));
-        let align = match self.alignment_tabular.as_str() {
-            "left" => AlignTab::L,
-            "right" => AlignTab::R,
-            "center" => AlignTab::C,
-            _ => AlignTab::L,
-        };
-
-        for _ in 0..titles.as_ref()?.len() {
-            let mut params = parameters.as_ref()?.clone();
-            let title = title.next();
-            let general_content = general_content.next();
-            let product_content = product_content.next();
-            let _tab = tab_creation::create_tabularx(
-                page,
-                params.len(),
-                title?,
-                &mut params,
-                &general_content?.to_vec(),
-                &product_content?.to_vec(),
-                nb_param,
-                &align,
-            );
-            // break;
+        // we iterate over tabulars
+        for category in product.categories.iter() {
+            self.category_table(page, category)?;
         }
 
         page.push(Element::UserDefined(String::from("\\vspace*{\\fill}")));
-        page.push(Element::UserDefined(String::from("{\\scriptsize
-        \\textbf{Disclaimer} This information and our technical advice - whether verbal, in writing or by way of trials - are given in good faith but without warranty, and this also applies where proprietary rights of third parties are involved. Our advice does not release you from the obligation to check its validity and to test our products as to their suitability for the intended processes and uses. The application, use and processing of our products and the products manufactured by you on the basis of our technical advice are beyond our control and, therefore, entirely your own responsibility. Our products are sold in accordance with our General Conditions of Sale and Delivery \\\\ \n BIOTEC Biologische Naturverpackungen GmbH \\& Co. KG · Werner-Heisenberg-Str. 32 · D.46446 Emmerich \\hfill \\textbf{T} +49 2822 92510\\qquad \\textbf{W} biotec.de}")));
+        if let Some(bottom) = branding.bottom() {
+            page.push(Element::UserDefined(bottom));
+        }
         page.push(Element::ClearPage);
-        Some(())
+        Ok(())
     }
 }
 
@@ -341,19 +787,33 @@ impl Default for PdfFile {
             output: String::from("output/"),
             source: String::from("sources/BIOTEC.xlsx"),
             worksheet: String::from("Master - Rigid Overview "),
-            products: vec!["BIOPLAST 800".to_string()],
+            sheets: Vec::new(),
+            data: String::new(),
+            database: None,
+            matching: LabelMatching::Exact,
+            orientation: Orientation::Rows,
+            number_format: NumberFormat::default(),
+            formats: HashMap::new(),
+            error_cells: ErrorPolicy::Warn,
+            error_placeholder: default_error_placeholder(),
+            products: vec![Label::from("BIOPLAST 800")],
             categories: vec![
-                String::from("Properties"),
-                String::from("Mechanical Properties"),
-                String::from("Compostable Certification"),
+                Label::from("Properties"),
+                Label::from("Mechanical Properties"),
+                Label::from("Compostable Certification"),
             ],
             parameters: vec![
-                String::from("Parameters"),
-                String::from("Certification"),
-                String::from("Unit"),
-                String::from("Descriptions"),
-                String::from("Standart"),
+                Label::from("Parameters"),
+                Label::from("Certification"),
+                Label::from("Unit"),
+                Label::from("Descriptions"),
+                Label::from("Standart"),
             ],
+            sub_parameters: None,
+            lookup: None,
+            variants: Vec::new(),
+            branding: None,
+            template: None,
         }
     }
 }
@@ -365,188 +825,876 @@ impl PdfFile {
             output: String::new(),
             source: String::new(),
             worksheet: String::new(),
+            sheets: Vec::new(),
+            data: String::new(),
+            database: None,
+            matching: LabelMatching::Exact,
+            orientation: Orientation::Rows,
+            number_format: NumberFormat::default(),
+            formats: HashMap::new(),
+            error_cells: ErrorPolicy::Warn,
+            error_placeholder: default_error_placeholder(),
             products: Vec::new(),
             categories: Vec::new(),
             parameters: Vec::new(),
+            sub_parameters: None,
+            lookup: None,
+            variants: Vec::new(),
+            branding: None,
+            template: None,
         }
     }
     pub fn is_empty(self) -> bool {
         self.products.is_empty()
     }
 
-    // Function to return a workbook
-    pub fn get_workbook(&self) -> Result<Xlsx<BufReader<File>>, Box<dyn Error>> {
-        let workbook: Xlsx<_> = open_workbook(&self.source)?;
-        Ok(workbook)
+    pub fn get_pdf_name(&self) -> &String {
+        &self.pdf_name
     }
 
-    pub fn search_cells_coordinates(&self, field: TabParameters) -> Option<Vec<(usize, usize)>> {
-        let mut workbook = self.get_workbook().ok()?;
-        let mut output: Vec<(usize, usize)> = Vec::new();
-        let field = match field {
+    pub fn get_error_policy(&self) -> ErrorPolicy {
+        self.error_cells
+    }
+
+    /// Return every worksheet the pdf file reads from.
+    /// `source` and `worksheet` come first, followed by the `sheets` list.
+    pub fn sheet_sources(&self) -> Vec<SheetSource> {
+        let mut sources: Vec<SheetSource> = Vec::new();
+        if !self.source.is_empty() {
+            sources.push(SheetSource {
+                source: self.source.clone(),
+                worksheet: self.worksheet.clone(),
+            });
+        }
+        sources.extend(self.sheets.iter().cloned());
+        sources
+    }
+
+    /// Return the shared models of the worksheets of this pdf file
+    pub fn get_sheets(&self, sheets: &mut SheetCache) -> Result<Vec<Rc<Sheet>>, GradeError> {
+        self.sheet_sources()
+            .iter()
+            .map(|sheet| sheets.get_oriented(&sheet.source, &sheet.worksheet, self.orientation))
+            .collect()
+    }
+
+    fn get_labels(&self, field: TabParameters) -> &Vec<Label> {
+        match field {
             TabParameters::Product => &self.products,
             TabParameters::Parameter => &self.parameters,
             TabParameters::Category => &self.categories,
+        }
+    }
+
+    /// Return the labels of `field` with the matching mode of the pdf file
+    fn get_matchers(
+        &self,
+        field: TabParameters,
+    ) -> Result<Vec<(&Label, LabelMatcher)>, GradeError> {
+        self.get_labels(field)
+            .iter()
+            .map(|label| Ok((label, LabelMatcher::from_label(label, self.matching)?)))
+            .collect()
+    }
+
+    /// Return the coordinates of the labels of `field` found in one sheet,
+    /// in the order of the sheet. A label can be found only once per sheet,
+    /// unless it is a glob or a regex.
+    pub fn search_cells_coordinates(
+        &self,
+        sheet: &Sheet,
+        field: TabParameters,
+    ) -> Result<Vec<(usize, usize)>, GradeError> {
+        let mut output: Vec<(usize, usize)> = Vec::new();
+        for (label, matcher) in self.get_matchers(field)? {
+            let found = sheet.search(&matcher)?;
+            if found.len() > 1 && !matcher.is_pattern() {
+                return Err(GradeError::DuplicateLabel {
+                    label: label.to_string(),
+                    cells: found.iter().map(|cell| sheet.address(*cell)).collect(),
+                });
+            }
+            output.extend(found);
+        }
+        output.sort_unstable();
+        output.dedup();
+        if let TabParameters::Product = field {
+            // a pattern can match several cells of a product row, the first
+            // one is the product name
+            output.dedup_by_key(|cell| cell.0);
+        }
+        Ok(output)
+    }
+
+    /// Search the labels of `field` in every sheet, and return the coordinates
+    /// found sheet by sheet. There must be labels, every label must be found
+    /// in one of the sheets, and a product can only be in one of them.
+    pub fn search_sheets_coordinates(
+        &self,
+        sheets: &[Rc<Sheet>],
+        field: TabParameters,
+    ) -> Result<Vec<Vec<(usize, usize)>>, GradeError> {
+        let (labels, name) = match field {
+            TabParameters::Product => (&self.products, "products"),
+            TabParameters::Category => (&self.categories, "categories"),
+            TabParameters::Parameter => (&self.parameters, "parameters"),
         };
-        match workbook.worksheet_range(&self.worksheet) {
-            Some(Ok(range)) => {
-                for row in 0..range.get_size().0 {
-                    for col in 0..range.get_size().1 {
-                        let value = range.get_value((row as u32, col as u32));
-                        if value != Some(&DataType::Empty) {
-                            for category in field {
-                                if &value?.to_string() == category {
-                                    output.push((row, col));
-                                }
-                            }
-                        }
+        if labels.is_empty() {
+            return Err(GradeError::EmptyLabels {
+                pdf_name: self.pdf_name.clone(),
+                field: name.to_string(),
+            });
+        }
+        let mut missing: Vec<MissingLabel> = Vec::new();
+        for (label, matcher) in self.get_matchers(field)? {
+            let mut cells: Vec<CellAddress> = Vec::new();
+            for sheet in sheets {
+                cells.extend(
+                    sheet
+                        .search(&matcher)?
+                        .into_iter()
+                        .map(|cell| sheet.address(cell)),
+                );
+            }
+            if cells.is_empty() {
+                // the category of a scoped label must be in one of the sheets
+                for (category, scope) in matcher.scopes() {
+                    if sheets
+                        .iter()
+                        .all(|sheet| sheet.search(scope).is_ok_and(|found| found.is_empty()))
+                    {
+                        return Err(GradeError::ScopeNotFound {
+                            label: label.to_string(),
+                            category: category.to_string(),
+                        });
                     }
                 }
+                missing.push(Sheet::suggest(label, sheets));
+                continue;
+            }
+            if let TabParameters::Product = field {
+                if cells.len() > 1 && !matcher.is_pattern() {
+                    return Err(GradeError::DuplicateLabel {
+                        label: label.to_string(),
+                        cells,
+                    });
+                }
             }
-            Some(Err(e)) => println!("{e:?}"),
-            None => println!("Sheets name unknown. Maybe check the name in the config file"),
         }
-        assert_eq!(output.len(), field.len());
-        if output.is_empty() {
-            return None;
+        if !missing.is_empty() {
+            return Err(GradeError::LabelNotFound {
+                labels: missing,
+                sheets: self.sheet_sources(),
+            });
         }
-        Some(output)
+        sheets
+            .iter()
+            .map(|sheet| self.search_cells_coordinates(sheet, field))
+            .collect()
     }
 
     /// Search the range of categories, to know where to stop
     /// Take the beginning corrdinates of categories, and return the end coordinates
     pub fn get_parameters_range(
         &self,
-        categories_coord: &Option<Vec<(usize, usize)>>,
-    ) -> Option<Vec<(usize, usize)>> {
-        if categories_coord.is_none() {
-            return None;
-        }
-        let mut end_categories: Vec<(usize, usize)> = vec![];
-        let mut workbook = self.get_workbook().ok()?;
-
-        match workbook.worksheet_range(&self.worksheet) {
-            Some(Ok(range)) => {
-                for (category_row, category_col) in categories_coord.as_ref()?.into_iter() {
-                    let mut col = category_col + 1;
-                    loop {
-                        if range.get_value((*category_row as u32, col as u32))
-                            != Some(&DataType::Empty)
-                            || col > range.get_size().1
-                        {
-                            break;
-                        }
-                        col += 1;
+        sheet: &Sheet,
+        categories_coord: &[(usize, usize)],
+    ) -> Vec<(usize, usize)> {
+        categories_coord
+            .iter()
+            .map(|category| sheet.category_end(*category))
+            .collect()
+    }
+
+    /// Search the row of the second level of the parameter names in a sheet
+    pub fn search_sub_parameters(
+        &self,
+        sheet: &Sheet,
+    ) -> Result<Option<(usize, usize)>, GradeError> {
+        let label = match &self.sub_parameters {
+            Some(label) => label,
+            None => return Ok(None),
+        };
+        let matcher = LabelMatcher::from_label(label, self.matching)?;
+        match sheet.search(&matcher)?[..] {
+            [] => Ok(None),
+            [cell] => Ok(Some(cell)),
+            ref cells => Err(GradeError::DuplicateLabel {
+                label: label.to_string(),
+                cells: cells.iter().map(|cell| sheet.address(*cell)).collect(),
+            }),
+        }
+    }
+
+    /// Return the group of every column of the categories with the second
+    /// level name of the column, as (group, name). A parameter name spans its
+    /// merged cells or the columns up to the next name, and only the columns
+    /// with a second level name are grouped. Like the parameters, the names
+    /// are read at the rows of their labels from the row of the category.
+    pub fn get_parameter_groups(
+        &self,
+        sheet: &Sheet,
+        start_categ_coord: &[(usize, usize)],
+        end_categ_coord: &[(usize, usize)],
+        id_line: &[(usize, usize)],
+    ) -> Result<Vec<Vec<Option<ParameterGroup>>>, GradeError> {
+        let rows = match (id_line.first(), self.search_sub_parameters(sheet)?) {
+            (Some(names), Some(sub_names)) => Some((names.0, sub_names.0)),
+            _ => None,
+        };
+        let mut output: Vec<Vec<Option<ParameterGroup>>> = Vec::new();
+        let it = start_categ_coord.iter().zip(end_categ_coord.iter());
+        for (start_coord, end_coord) in it {
+            let mut groups: Vec<Option<ParameterGroup>> = Vec::new();
+            // the current group, with its last column
+            let mut group: Option<(String, usize)> = None;
+            for col in start_coord.1..end_coord.1 + 1 {
+                let (names_row, sub_names_row) = match rows {
+                    Some((names, sub_names)) => (start_coord.0 + names, start_coord.0 + sub_names),
+                    None => {
+                        groups.push(None);
+                        continue;
+                    }
+                };
+                let name = sheet.get_cell((names_row, col))?;
+                if !name.is_empty() {
+                    let (_, end_col) = sheet.category_end((names_row, col));
+                    group = Some((self.format_cell(&self.number_format, name), end_col));
+                }
+                let sub_name = sheet.get_cell((sub_names_row, col))?;
+                groups.push(match &group {
+                    Some((group, end_col)) if col <= *end_col && !sub_name.is_empty() => Some((
+                        group.to_string(),
+                        self.format_cell(&self.number_format, sub_name),
+                    )),
+                    _ => None,
+                });
+            }
+            output.push(groups);
+        }
+        Ok(output)
+    }
+
+    /// Return the format of a column, found from the texts of its parameters
+    /// (its name, its unit...) and completed by the default format
+    pub fn column_format(&self, column_parameters: &[DataType]) -> NumberFormat {
+        column_parameters
+            .iter()
+            .find_map(|parameter| self.formats.get(parameter.to_string().trim()))
+            .map_or_else(
+                || self.number_format.clone(),
+                |format| format.or(&self.number_format),
+            )
+    }
+
+    /// Return the report of the pdf file, read from its data file or its
+    /// database when it has one, or else from its sheets
+    /// The variants are then made, and the fields of the lookup sheet joined to
+    /// the products.
+    pub fn get_report(&self, sheets: &mut SheetCache) -> Result<Report, GradeError> {
+        let mut report = if let Some(database) = &self.database {
+            let report = self.database_report(database, &database.read()?)?;
+            let products = self.select_products(report.products, &database.path)?;
+            Report { products, ..report }
+        } else if self.data.is_empty() {
+            self.extract_report(&self.get_sheets(sheets)?)?
+        } else {
+            let mut report = data_file::read_report(&self.data)?;
+            report.pdf_name = self.pdf_name.clone();
+            report.products = self.select_products(report.products, &self.data)?;
+            report
+        };
+        self.add_variants(&mut report.products)?;
+        if let Some(lookup) = &self.lookup {
+            let sheet = sheets.get(&lookup.source, &lookup.worksheet)?;
+            self.join_lookup(&sheet, lookup, &mut report.products)?;
+        }
+        Ok(report)
+    }
+
+    /// Add the variants to the products: a copy of the base product with the
+    /// non-empty values of the row of the variant, which it replaces, and then
+    /// the overrides. A variant without a row comes after its base product
+    /// and the variants of the base before it. An override must name a single
+    /// parameter of the product, and only the values changed from the base
+    /// are highlighted.
+    pub fn add_variants(&self, products: &mut Vec<ReportProduct>) -> Result<(), GradeError> {
+        for variant in self.variants.iter() {
+            let base = products
+                .iter()
+                .find(|p| p.name.text.trim() == variant.base.trim())
+                .ok_or_else(|| {
+                    let names = products
+                        .iter()
+                        .map(|p| (p.name.text.as_str(), &p.name.address));
+                    GradeError::LabelNotFound {
+                        labels: vec![MissingLabel {
+                            label: variant.base.clone(),
+                            suggestions: suggest::closest(&variant.base, names)
+                                .into_iter()
+                                .map(|(name, address)| {
+                                    (name.to_string(), address.iter().cloned().collect())
+                                })
+                                .collect(),
+                        }],
+                        sheets: self.sheet_sources(),
                     }
-                    end_categories.push((*category_row, col - 1));
+                })?;
+            let mut merged = base.clone();
+            let sparse = products
+                .iter()
+                .position(|p| p.name.text.trim() == variant.name.trim());
+            merged.name = match sparse {
+                Some(i) => products[i].name.clone(),
+                None => ReportCell {
+                    text: variant.name.clone(),
+                    value: CellValue::Text(variant.name.clone()),
+                    address: None,
+                },
+            };
+            // the rows of the variant are matched by category and parameter
+            let sparse_rows = sparse.iter().flat_map(|i| {
+                products[*i].categories.iter().flat_map(|category| {
+                    category
+                        .rows
+                        .iter()
+                        .filter(|row| row.value.value != CellValue::Empty)
+                        .map(|row| (&category.title.text, row))
+                })
+            });
+            for (title, sparse_row) in sparse_rows {
+                let row = merged
+                    .categories
+                    .iter_mut()
+                    .filter(|category| category.title.text == *title)
+                    .flat_map(|category| category.rows.iter_mut())
+                    .find(|row| row.name() == sparse_row.name());
+                if let Some(row) = row {
+                    row.value = sparse_row.value.clone();
+                }
+            }
+            for (parameter, value) in variant.overrides.iter() {
+                let mut rows = merged.categories.iter_mut().flat_map(|category| {
+                    let title = &category.title.text;
+                    category.rows.iter_mut().map(move |row| (title, row))
+                });
+                let row = rows.find(|(_, row)| row.name().trim() == parameter.trim());
+                // a parameter name must name a single row of the product
+                let others: Vec<String> = rows
+                    .filter(|(_, row)| row.name().trim() == parameter.trim())
+                    .map(|(title, _)| title.to_string())
+                    .collect();
+                let row = match row {
+                    Some((title, _)) if !others.is_empty() => {
+                        return Err(GradeError::AmbiguousOverride {
+                            variant: variant.name.clone(),
+                            parameter: parameter.to_string(),
+                            categories: [title.to_string()].into_iter().chain(others).collect(),
+                        });
+                    }
+                    Some((_, row)) => row,
+                    None => {
+                        let names: Vec<String> = merged
+                            .categories
+                            .iter()
+                            .flat_map(|category| category.rows.iter().map(|row| row.name()))
+                            .collect();
+                        let candidates = names.iter().map(|name| (name.as_str(), ()));
+                        return Err(GradeError::LabelNotFound {
+                            labels: vec![MissingLabel {
+                                label: parameter.to_string(),
+                                suggestions: suggest::closest(parameter, candidates)
+                                    .into_iter()
+                                    .map(|(name, _)| (name.to_string(), Vec::new()))
+                                    .collect(),
+                            }],
+                            sheets: self.sheet_sources(),
+                        });
+                    }
+                };
+                let column: Vec<DataType> = row
+                    .parameters
+                    .iter()
+                    .map(|cell| DataType::from(&cell.value))
+                    .collect();
+                row.value = ReportCell {
+                    text: self
+                        .format_cell(&self.column_format(&column), &DataType::from(&value.value)),
+                    value: value.value.clone(),
+                    address: None,
+                };
+            }
+            // only the values written differently from the base are changed
+            if variant.highlight {
+                let base_rows = base.categories.iter().flat_map(|c| c.rows.iter());
+                let rows = merged.categories.iter_mut().flat_map(|c| c.rows.iter_mut());
+                for (row, base_row) in rows.zip(base_rows) {
+                    row.highlighted |= row.value.text != base_row.value.text;
+                }
+            }
+            match sparse {
+                Some(i) => products[i] = merged,
+                None => {
+                    // after the base, and after the variants of the base
+                    // already placed
+                    let mut position = products
+                        .iter()
+                        .position(|p| p.name.text.trim() == variant.base.trim())
+                        .map_or(products.len(), |base| base + 1);
+                    while products.get(position).is_some_and(|product| {
+                        self.variants.iter().any(|placed| {
+                            placed.base.trim() == variant.base.trim()
+                                && placed.name.trim() == product.name.text.trim()
+                        })
+                    }) {
+                        position += 1;
+                    }
+                    products.insert(position, merged);
                 }
             }
-            Some(Err(e)) => println!("{e:?}"),
-            None => println!("Sheets name unknown. Maybe check the name in the config file"),
         }
-        Some(end_categories)
+        Ok(())
     }
-    /// Return the values at a given coordinates
-    pub fn get_values_at(
+
+    /// Join the fields of the lookup sheet to the products: the cells of the
+    /// line whose key is the product name, named by the header of their column.
+    /// A product without a line keeps no field, and the empty cells are left
+    /// out.
+    pub fn join_lookup(
         &self,
-        begin_categories: &Option<Vec<(usize, usize)>>,
-    ) -> Option<Vec<String>> {
-        if begin_categories.is_none() {
-            return None;
-        }
-        let mut workbook = self.get_workbook().ok()?;
-        let mut output: Vec<String> = vec![];
-
-        match workbook.worksheet_range(&self.worksheet) {
-            Some(Ok(range)) => {
-                for category in begin_categories.as_ref()? {
-                    let (a, b) = category;
-                    output.push(range.get_value((*a as u32, *b as u32))?.to_string())
+        sheet: &Rc<Sheet>,
+        lookup: &LookupSheet,
+        products: &mut [ReportProduct],
+    ) -> Result<(), GradeError> {
+        let sheet_rc = std::slice::from_ref(sheet);
+        let matcher = LabelMatcher::from_label(&lookup.key, self.matching)?;
+        let key = match sheet.search(&matcher)?[..] {
+            [cell] => cell,
+            [] => {
+                return Err(GradeError::LabelNotFound {
+                    labels: vec![Sheet::suggest(&lookup.key, sheet_rc)],
+                    sheets: vec![SheetSource {
+                        source: lookup.source.clone(),
+                        worksheet: lookup.worksheet.clone(),
+                    }],
+                })
+            }
+            ref cells => {
+                return Err(GradeError::DuplicateLabel {
+                    label: lookup.key.to_string(),
+                    cells: cells.iter().map(|cell| sheet.address(*cell)).collect(),
+                })
+            }
+        };
+
+        let mut headers: Vec<(String, usize)> = Vec::new();
+        for cell in sheet.row_cells(key.0) {
+            let header = sheet.get_cell(cell)?.to_string().trim().to_string();
+            if cell != key && (lookup.fields.is_empty() || lookup.fields.contains(&header)) {
+                headers.push((header, cell.1));
+            }
+        }
+        let missing: Vec<MissingLabel> = lookup
+            .fields
+            .iter()
+            .filter(|field| !headers.iter().any(|(header, _)| header == *field))
+            .map(|field| Sheet::suggest(&Label::from(field.as_str()), sheet_rc))
+            .collect();
+        if !missing.is_empty() {
+            return Err(GradeError::LabelNotFound {
+                labels: missing,
+                sheets: vec![SheetSource {
+                    source: lookup.source.clone(),
+                    worksheet: lookup.worksheet.clone(),
+                }],
+            });
+        }
+        // the fields keep the order of the configuration, or else of the sheet
+        if !lookup.fields.is_empty() {
+            headers.sort_by_key(|(header, _)| lookup.fields.iter().position(|f| f == header));
+        }
+
+        for product in products.iter_mut() {
+            let line = sheet
+                .column_cells(key.1)
+                .into_iter()
+                .filter(|cell| cell.0 > key.0)
+                .find(|cell| {
+                    sheet
+                        .get_value(*cell)
+                        .map(|v| v.to_string().trim().to_string())
+                        == Some(product.name.text.trim().to_string())
+                });
+            let row = match line {
+                Some((row, _)) => row,
+                None => continue,
+            };
+            product.fields.clear();
+            for (header, col) in headers.iter() {
+                let value = sheet.get_cell((row, *col))?;
+                match value {
+                    DataType::Empty => continue,
+                    DataType::Error(error) if self.error_cells == ErrorPolicy::Fail => {
+                        return Err(GradeError::ErrorCell(ErrorCell {
+                            address: sheet.address((row, *col)),
+                            error: error.to_string(),
+                        }))
+                    }
+                    _ => (),
                 }
+                let format = self.column_format(&[DataType::String(header.to_string())]);
+                product.fields.push(ReportField {
+                    name: header.to_string(),
+                    value: self.report_cell(sheet, (row, *col), &format)?,
+                });
             }
-            Some(Err(e)) => println!("{e:?}"),
-            None => println!("Sheets name unknown. Maybe check the name in the config file"),
         }
-        Some(output)
+        Ok(())
     }
 
-    pub fn get_parameters_by_id(
+    /// Build the report of the lines of a database: a page per product, a
+    /// table per category and a row per parameter, in the order of the lines.
+    /// The headers of the tables are the names of the parameter and unit
+    /// columns.
+    pub fn database_report(
         &self,
-        start_categ_coord: &Option<Vec<(usize, usize)>>,
-        end_categ_coord: &Option<Vec<(usize, usize)>>,
-        id_line: &Option<Vec<(usize, usize)>>,
-    ) -> Option<Vec<Vec<String>>> {
-        if start_categ_coord.is_none() || end_categ_coord.is_none() || id_line.is_none() {
-            return None;
-        }
-        assert_eq!(
-            start_categ_coord.as_ref()?.len(),
-            end_categ_coord.as_ref()?.len()
-        );
+        database: &DatabaseSource,
+        table: &DatabaseTable,
+    ) -> Result<Report, GradeError> {
+        let columns = &database.columns;
+        let product_col = table.column(database, &columns.product)?;
+        let category_col = table.column(database, &columns.category)?;
+        let value_col = table.column(database, &columns.value)?;
+        let mut parameter_cols = vec![table.column(database, &columns.parameter)?];
+        if let Some(unit) = &columns.unit {
+            parameter_cols.push(table.column(database, unit)?);
+        }
+        let group_col = match &columns.group {
+            Some(group) => Some(table.column(database, group)?),
+            None => None,
+        };
 
-        let id_line: Vec<usize> = id_line.as_ref()?.iter().map(|v| v.0).collect();
-        let mut workbook = self.get_workbook().ok()?;
-        let mut output: Vec<Vec<String>> = vec![];
-        //
-        match workbook.worksheet_range(&self.worksheet) {
-            Some(Ok(range)) => {
-                let it = start_categ_coord
-                    .as_ref()?
+        // the column names are the first line, as in a sheet
+        let address = |row: usize, col: usize| CellAddress {
+            source: database.path.clone(),
+            worksheet: database.name(),
+            cell: (row, col),
+        };
+        let cell = |row: usize, col: usize, format: &NumberFormat| {
+            let value = match row {
+                0 => DataType::String(table.columns[col].clone()),
+                _ => table.lines[row - 1][col].clone(),
+            };
+            ReportCell {
+                text: self.format_cell(format, &value),
+                value: CellValue::from(&value),
+                address: Some(address(row, col)),
+            }
+        };
+        let headers: Vec<ReportCell> = parameter_cols
+            .iter()
+            .map(|col| cell(0, *col, &self.number_format))
+            .collect();
+
+        let mut products: Vec<ReportProduct> = Vec::new();
+        for (id_line, line) in table.lines.iter().enumerate() {
+            let row = id_line + 1;
+            let name = line[product_col].to_string();
+            let product = match products.iter().position(|p| p.name.text == name) {
+                Some(i) => &mut products[i],
+                None => {
+                    products.push(ReportProduct {
+                        name: cell(row, product_col, &self.number_format),
+                        fields: Vec::new(),
+                        categories: Vec::new(),
+                    });
+                    products.last_mut().unwrap()
+                }
+            };
+            let title = line[category_col].to_string();
+            let category = match product
+                .categories
+                .iter()
+                .position(|c| c.title.text == title)
+            {
+                Some(i) => &mut product.categories[i],
+                None => {
+                    product.categories.push(ReportCategory {
+                        title: cell(row, category_col, &self.number_format),
+                        headers: headers.clone(),
+                        rows: Vec::new(),
+                    });
+                    product.categories.last_mut().unwrap()
+                }
+            };
+            let column: Vec<DataType> = parameter_cols
+                .iter()
+                .map(|col| line[*col].clone())
+                .collect();
+            let format = self.column_format(&column);
+            category.rows.push(ReportRow {
+                group: group_col
+                    .map(|col| line[col].to_string())
+                    .filter(|group| !group.is_empty()),
+                parameters: parameter_cols
                     .iter()
-                    .zip(end_categ_coord.as_ref()?.iter());
-                for (_, (start_coord, end_coord)) in it.enumerate() {
-                    let mut parameters: Vec<String> = vec![];
-                    for col in start_coord.1..end_coord.1 + 1 {
-                        for line in id_line.iter() {
-                            parameters.push(
-                                range
-                                    .get_value(((start_coord.0 + line) as u32, col as u32))?
-                                    .to_string(),
-                            )
+                    .map(|col| cell(row, *col, &self.number_format))
+                    .collect(),
+                value: cell(row, value_col, &format),
+                highlighted: false,
+            });
+        }
+        Ok(Report {
+            pdf_name: self.pdf_name.clone(),
+            products,
+        })
+    }
+
+    /// Keep the products of a data file or a database matching the product
+    /// labels, every product when there is no label. Every label must match a
+    /// product.
+    fn select_products(
+        &self,
+        products: Vec<ReportProduct>,
+        source: &str,
+    ) -> Result<Vec<ReportProduct>, GradeError> {
+        if self.products.is_empty() {
+            return Ok(products);
+        }
+        let matchers = self.get_matchers(TabParameters::Product)?;
+        let mut missing: Vec<MissingLabel> = Vec::new();
+        for (label, matcher) in matchers.iter() {
+            if products.iter().any(|p| matcher.is_match(&p.name.text)) {
+                continue;
+            }
+            let names = products
+                .iter()
+                .map(|p| (p.name.text.as_str(), &p.name.address));
+            missing.push(MissingLabel {
+                label: label.to_string(),
+                suggestions: suggest::closest(&label.to_string(), names)
+                    .into_iter()
+                    .map(|(name, address)| (name.to_string(), address.iter().cloned().collect()))
+                    .collect(),
+            });
+        }
+        if !missing.is_empty() {
+            return Err(GradeError::LabelNotFound {
+                labels: missing,
+                sheets: vec![SheetSource {
+                    source: source.to_string(),
+                    worksheet: String::new(),
+                }],
+            });
+        }
+        Ok(products
+            .into_iter()
+            .filter(|p| matchers.iter().any(|(_, m)| m.is_match(&p.name.text)))
+            .collect())
+    }
+
+    /// Read the report of the pdf file from its sheets: every product found,
+    /// with the parameters and the values of its categories. A sheet with
+    /// products must have categories and parameters. It fails on the first
+    /// error cell with the fail policy.
+    pub fn extract_report(&self, sheets: &[Rc<Sheet>]) -> Result<Report, GradeError> {
+        let categories_coords = self.search_sheets_coordinates(sheets, TabParameters::Category)?;
+        let parameters_coords = self.search_sheets_coordinates(sheets, TabParameters::Parameter)?;
+        let products_coords = self.search_sheets_coordinates(sheets, TabParameters::Product)?;
+
+        // each product is read from the sheet where it was found
+        let mut products: Vec<ReportProduct> = Vec::new();
+        for (id_sheet, sheet) in sheets.iter().enumerate() {
+            let products_coord = &products_coords[id_sheet];
+            let begin_categories_coord = &categories_coords[id_sheet];
+            let parameters_coord = &parameters_coords[id_sheet];
+            if products_coord.is_empty() {
+                continue;
+            }
+            // the products of a sheet are read with the labels of that sheet
+            if begin_categories_coord.is_empty() {
+                return Err(self.labels_not_found(sheet, TabParameters::Category));
+            }
+            if parameters_coord.is_empty() {
+                return Err(self.labels_not_found(sheet, TabParameters::Parameter));
+            }
+            let end_categories_coord = self.get_parameters_range(sheet, begin_categories_coord);
+            let groups = self.get_parameter_groups(
+                sheet,
+                begin_categories_coord,
+                &end_categories_coord,
+                parameters_coord,
+            )?;
+            let sub_parameters = self.search_sub_parameters(sheet)?;
+            for product_coord in products_coord.iter() {
+                self.get_error_cells(
+                    sheet,
+                    *product_coord,
+                    begin_categories_coord,
+                    &end_categories_coord,
+                    parameters_coord,
+                )?;
+                let mut categories: Vec<ReportCategory> = Vec::new();
+                let it = begin_categories_coord
+                    .iter()
+                    .zip(end_categories_coord.iter())
+                    .zip(groups.iter());
+                for ((start_coord, end_coord), groups) in it {
+                    let mut rows: Vec<ReportRow> = Vec::new();
+                    for (col, group) in (start_coord.1..end_coord.1 + 1).zip(groups.iter()) {
+                        let lines: Vec<(usize, usize)> = parameters_coord
+                            .iter()
+                            .map(|line| (start_coord.0 + line.0, col))
+                            .collect();
+                        let column = lines
+                            .iter()
+                            .map(|cell| sheet.get_cell(*cell).cloned())
+                            .collect::<Result<Vec<DataType>, GradeError>>()?;
+                        let mut parameters = lines
+                            .iter()
+                            .map(|cell| self.report_cell(sheet, *cell, &self.number_format))
+                            .collect::<Result<Vec<ReportCell>, GradeError>>()?;
+                        // a grouped parameter is named by its second level name
+                        if let (Some(_), Some(sub_names), Some(name)) =
+                            (group, sub_parameters, parameters.first_mut())
+                        {
+                            let sub_names_row = start_coord.0 + sub_names.0;
+                            *name =
+                                self.report_cell(sheet, (sub_names_row, col), &self.number_format)?;
                         }
+                        let format = self.column_format(&column);
+                        rows.push(ReportRow {
+                            group: group.as_ref().map(|(group, _)| group.to_string()),
+                            parameters,
+                            value: self.report_cell(sheet, (product_coord.0, col), &format)?,
+                            highlighted: false,
+                        });
                     }
-                    output.push(parameters);
+                    categories.push(ReportCategory {
+                        title: self.report_cell(sheet, *start_coord, &self.number_format)?,
+                        headers: parameters_coord
+                            .iter()
+                            .map(|cell| self.report_cell(sheet, *cell, &self.number_format))
+                            .collect::<Result<Vec<ReportCell>, GradeError>>()?,
+                        rows,
+                    });
                 }
+                products.push(ReportProduct {
+                    name: self.report_cell(sheet, *product_coord, &self.number_format)?,
+                    fields: Vec::new(),
+                    categories,
+                });
             }
-            Some(Err(e)) => println!("{e:?}"),
-            None => println!("Sheets name unknown. Maybe check the name in the config file"),
         }
-        Some(output)
+        Ok(Report {
+            pdf_name: self.pdf_name.clone(),
+            products,
+        })
+    }
+
+    /// Return the error of the labels of `field` missing in a sheet where
+    /// products are found
+    fn labels_not_found(&self, sheet: &Rc<Sheet>, field: TabParameters) -> GradeError {
+        GradeError::LabelNotFound {
+            labels: self
+                .get_labels(field)
+                .iter()
+                .map(|label| Sheet::suggest(label, std::slice::from_ref(sheet)))
+                .collect(),
+            sheets: vec![SheetSource {
+                source: sheet.source().to_string(),
+                worksheet: sheet.worksheet().to_string(),
+            }],
+        }
+    }
+
+    /// Read a cell of a sheet into the report, written with a format
+    fn report_cell(
+        &self,
+        sheet: &Sheet,
+        cell: (usize, usize),
+        format: &NumberFormat,
+    ) -> Result<ReportCell, GradeError> {
+        let value = sheet.get_cell(cell)?;
+        Ok(ReportCell {
+            text: self.format_cell(format, value),
+            value: CellValue::from(value),
+            address: Some(sheet.address(cell)),
+        })
+    }
+
+    /// Write a value with a format, and the errors with the error policy
+    fn format_cell(&self, format: &NumberFormat, value: &DataType) -> String {
+        match (value, self.error_cells) {
+            (DataType::Error(_), ErrorPolicy::Placeholder) => self.error_placeholder.clone(),
+            (DataType::Error(_), ErrorPolicy::Blank) => String::new(),
+            _ => format.format(value),
+        }
     }
 
-    pub fn get_values_from_parameters(
+    /// Return the error cells of the page of a product: its name, the titles
+    /// and the parameters of the categories, and its values.
+    /// It fails on the first one with the fail policy.
+    pub fn get_error_cells(
         &self,
+        sheet: &Sheet,
         product_coordinates: (usize, usize),
-        start_categ_coord: &Option<Vec<(usize, usize)>>,
-        end_categ_coord: &Option<Vec<(usize, usize)>>,
-    ) -> Option<Vec<Vec<String>>> {
-        if start_categ_coord.is_none() || end_categ_coord.is_none() {
-            return None;
-        }
-        let mut workbook = self.get_workbook().ok()?;
-        let mut out: Vec<Vec<String>> = Vec::new();
-        match workbook.worksheet_range(&self.worksheet) {
-            Some(Ok(range)) => {
-                for param in 0..start_categ_coord.as_ref()?.len() {
-                    let mut parameters: Vec<String> = vec![];
-                    for y in start_categ_coord.as_ref()?.iter().nth(param)?.1
-                        ..end_categ_coord.as_ref()?.iter().nth(param)?.1 + 1
-                    {
-                        let x = product_coordinates.0;
-                        parameters.push(range.get_value((x as u32, y as u32))?.to_string());
-                    }
-                    out.push(parameters);
+        start_categ_coord: &[(usize, usize)],
+        end_categ_coord: &[(usize, usize)],
+        id_line: &[(usize, usize)],
+    ) -> Result<Vec<ErrorCell>, GradeError> {
+        let mut cells: Vec<(usize, usize)> = vec![product_coordinates];
+        let it = start_categ_coord.iter().zip(end_categ_coord.iter());
+        for (start_coord, end_coord) in it {
+            cells.push(*start_coord);
+            for col in start_coord.1..end_coord.1 + 1 {
+                cells.extend(id_line.iter().map(|line| (start_coord.0 + line.0, col)));
+                cells.push((product_coordinates.0, col));
+            }
+        }
+        let mut output: Vec<ErrorCell> = Vec::new();
+        for cell in cells {
+            if let DataType::Error(error) = sheet.get_cell(cell)? {
+                let error_cell = ErrorCell {
+                    address: sheet.address(cell),
+                    error: error.to_string(),
+                };
+                if self.error_cells == ErrorPolicy::Fail {
+                    return Err(GradeError::ErrorCell(error_cell));
                 }
+                output.push(error_cell);
             }
-            Some(Err(e)) => println!("{e:?}"),
-            None => println!("Sheets name unknown. Maybe check the name in the config file"),
         }
-        Some(out)
+        Ok(output)
+    }
+
+    /// Write the report in the output directory as `<pdf name>.json` and
+    /// `<pdf name>.csv`, and return the paths of the files
+    pub fn export_report(&self, report: &Report) -> Result<Vec<PathBuf>, GradeError> {
+        let out_path = PathBuf::from(&self.output);
+        std::fs::create_dir_all(&out_path)?;
+        let json_path = out_path.join(format!("{}.json", self.pdf_name));
+        export::write_json(report, &json_path)?;
+        let csv_path = out_path.join(format!("{}.csv", self.pdf_name));
+        export::write_csv(report, &csv_path)?;
+        Ok(vec![json_path, csv_path])
     }
 
     /// create and render pdf
-    pub fn create_and_render(&self, page: Document) -> Result<(), Box<dyn std::error::Error>> {
-        let render = print(&page)?;
+    pub fn create_and_render(&self, page: Document) -> Result<(), GradeError> {
+        let render = print(&page).map_err(|e| GradeError::Latex {
+            pdf_name: self.pdf_name.clone(),
+            message: e.to_string(),
+        })?;
+        self.create_from_tex(&render)
+    }
+
+    /// Write the LaTeX of the pdf in the output directory and compile it
+    pub fn create_from_tex(&self, render: &str) -> Result<(), GradeError> {
+        let latex_error = |message: String| GradeError::Latex {
+            pdf_name: self.pdf_name.clone(),
+            message,
+        };
 
         // let out_path = String::from(&format!("{}/{}.tex", self.output, self.pdf_name));
         let out_path = PathBuf::from(&self.output); //;
@@ -558,18 +1706,22 @@ impl PdfFile {
         let mut tex_f = PathBuf::from(&self.pdf_name);
         tex_f.set_extension("tex");
         // println!("{tex_f:?}");
-        let mut f: File = File::create(&out_path.join(tex_f))?;
+        let mut f: File = File::create(out_path.join(tex_f))?;
 
         write!(f, "{}", render)?;
 
-        std::process::Command::new("latexmk")
+        let status = std::process::Command::new("latexmk")
             .arg(out_path)
             .arg("-pdf")
             .arg(format!(
                 "--output-directory={}/",
                 self.output.replace("/", "")
             ))
-            .status()?;
+            .status()
+            .map_err(|e| latex_error(format!("cannot run latexmk: {e}")))?;
+        if !status.success() {
+            return Err(latex_error(format!("latexmk failed with {status}")));
+        }
         Ok(())
     }
 }
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::sheet::{parse_cell_name, parse_range, CellRange};
+use crate::GradeError;
+
+/// A label of the configuration: the text of a cell, or an anchor to cells of
+/// the workbook when the text is ambiguous.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Label {
+    Text(String),
+    /// A1 address of a cell, with its worksheet or not: "C5" or "'Overview'!C5"
+    Cell {
+        cell: String,
+    },
+    /// A name defined in the workbook
+    Name {
+        name: String,
+    },
+    /// The cells of one column of an Excel table, the first one by default
+    Table {
+        table: String,
+        #[serde(default)]
+        column: Option<String>,
+    },
+    /// A text searched only in a region of the sheet: the block of a category
+    /// (`within`) or a range of cells (`range`, like "A1:F10")
+    Scoped {
+        label: String,
+        #[serde(default)]
+        within: Option<String>,
+        #[serde(default)]
+        range: Option<String>,
+    },
+}
+
+impl From<&str> for Label {
+    fn from(text: &str) -> Self {
+        Label::Text(text.to_string())
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Label::Text(text) => write!(f, "{text}"),
+            Label::Cell { cell } => write!(f, "cell {cell}"),
+            Label::Name { name } => write!(f, "name {name}"),
+            Label::Table {
+                table,
+                column: Some(column),
+            } => write!(f, "table {table}[{column}]"),
+            Label::Table {
+                table,
+                column: None,
+            } => write!(f, "table {table}"),
+            Label::Scoped {
+                label,
+                within,
+                range,
+            } => {
+                write!(f, "{label}")?;
+                if let Some(within) = within {
+                    write!(f, " within {within:?}")?;
+                }
+                if let Some(range) = range {
+                    write!(f, " in {range}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How the labels of the configuration are compared to the text of the cells
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LabelMatching {
+    /// The cell text is exactly the label
+    #[default]
+    Exact,
+    /// Leading and trailing whitespaces are ignored
+    Trimmed,
+    /// Whitespaces around the text and the case are ignored
+    CaseInsensitive,
+    /// The label is a pattern where `*` is any text and `?` any character
+    Glob,
+    /// The label is a regular expression matching the whole cell text
+    Regex,
+}
+
+/// A label of the configuration, ready to be compared to the cells
+#[derive(Debug, Clone)]
+pub enum LabelMatcher {
+    Exact(String),
+    Trimmed(String),
+    CaseInsensitive(String),
+    Pattern(Regex),
+    Cell {
+        worksheet: Option<String>,
+        cell: (usize, usize),
+    },
+    Name(String),
+    Table {
+        table: String,
+        column: Option<String>,
+    },
+    Scoped {
+        matcher: Box<LabelMatcher>,
+        regions: Vec<Region>,
+    },
+}
+
+/// A region of a sheet where a label is searched
+#[derive(Debug, Clone)]
+pub enum Region {
+    /// A range of cells
+    Range(CellRange),
+    /// The block of a category: its columns, from its header to the last row
+    Within {
+        category: String,
+        matcher: Box<LabelMatcher>,
+    },
+}
+
+impl LabelMatcher {
+    /// Build the matcher of a label, the matching mode applies to text labels
+    pub fn from_label(label: &Label, matching: LabelMatching) -> Result<Self, GradeError> {
+        match label {
+            Label::Text(text) => LabelMatcher::new(text, matching),
+            Label::Cell { cell } => {
+                let (worksheet, address) = match cell.rsplit_once('!') {
+                    Some((worksheet, address)) => (
+                        Some(worksheet.trim_matches('\'').replace("''", "'")),
+                        address,
+                    ),
+                    None => (None, cell.as_str()),
+                };
+                let cell = parse_cell_name(address).ok_or_else(|| GradeError::InvalidPattern {
+                    pattern: cell.to_string(),
+                    message: String::from("not a cell address"),
+                })?;
+                Ok(LabelMatcher::Cell { worksheet, cell })
+            }
+            Label::Name { name } => Ok(LabelMatcher::Name(name.to_string())),
+            Label::Table { table, column } => Ok(LabelMatcher::Table {
+                table: table.to_string(),
+                column: column.clone(),
+            }),
+            Label::Scoped {
+                label,
+                within,
+                range,
+            } => {
+                let mut regions: Vec<Region> = Vec::new();
+                if let Some(within) = within {
+                    regions.push(Region::Within {
+                        category: within.to_string(),
+                        matcher: Box::new(LabelMatcher::new(within, matching)?),
+                    });
+                }
+                if let Some(range) = range {
+                    let cells = parse_range(range).ok_or_else(|| GradeError::InvalidPattern {
+                        pattern: range.to_string(),
+                        message: String::from("not a range of cells"),
+                    })?;
+                    regions.push(Region::Range(cells));
+                }
+                Ok(LabelMatcher::Scoped {
+                    matcher: Box::new(LabelMatcher::new(label, matching)?),
+                    regions,
+                })
+            }
+        }
+    }
+
+    pub fn new(label: &str, matching: LabelMatching) -> Result<Self, GradeError> {
+        let pattern = match matching {
+            LabelMatching::Exact => return Ok(LabelMatcher::Exact(label.to_string())),
+            LabelMatching::Trimmed => return Ok(LabelMatcher::Trimmed(label.trim().to_string())),
+            LabelMatching::CaseInsensitive => {
+                return Ok(LabelMatcher::CaseInsensitive(label.trim().to_lowercase()))
+            }
+            // a label without any wildcard or regex syntax is a plain label
+            LabelMatching::Glob if !label.contains(['*', '?']) => {
+                return Ok(LabelMatcher::Exact(label.to_string()))
+            }
+            LabelMatching::Regex if !label.contains(REGEX_SYNTAX) => {
+                return Ok(LabelMatcher::Exact(label.to_string()))
+            }
+            LabelMatching::Glob => glob_to_regex(label),
+            LabelMatching::Regex => label.to_string(),
+        };
+        let regex =
+            Regex::new(&format!("^(?:{pattern})$")).map_err(|e| GradeError::InvalidPattern {
+                pattern: label.to_string(),
+                message: e.to_string(),
+            })?;
+        Ok(LabelMatcher::Pattern(regex))
+    }
+
+    /// Check if the text of a cell matches the label. Anchors do not depend
+    /// on the text and never match.
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            LabelMatcher::Exact(label) => text == label,
+            LabelMatcher::Trimmed(label) => text.trim() == label,
+            LabelMatcher::CaseInsensitive(label) => text.trim().to_lowercase() == *label,
+            LabelMatcher::Pattern(regex) => regex.is_match(text),
+            _ => false,
+        }
+    }
+
+    /// Return the categories a scoped label is searched within, with their
+    /// matchers
+    pub fn scopes(&self) -> Vec<(&str, &LabelMatcher)> {
+        match self {
+            LabelMatcher::Scoped { regions, .. } => regions
+                .iter()
+                .filter_map(|region| match region {
+                    Region::Within { category, matcher } => Some((category.as_str(), &**matcher)),
+                    Region::Range(_) => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// A pattern, a named range or a table can match several cells, a plain
+    /// label or a cell address only one
+    pub fn is_pattern(&self) -> bool {
+        match self {
+            LabelMatcher::Scoped { matcher, .. } => matcher.is_pattern(),
+            _ => matches!(
+                self,
+                LabelMatcher::Pattern(_) | LabelMatcher::Name(_) | LabelMatcher::Table { .. }
+            ),
+        }
+    }
+}
+
+/// The characters giving a regular expression more than its plain text
+const REGEX_SYNTAX: [char; 14] = [
+    '\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$',
+];
+
+/// Convert a glob pattern into a regular expression
+fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::new();
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use zip::ZipArchive;
+
+use crate::sheet::{parse_range, CellRange};
+
+type Archive = ZipArchive<BufReader<File>>;
+
+/// Return the merged cells of a worksheet of a xlsx or xlsm workbook, which
+/// calamine does not read. None when the workbook parts cannot be read.
+pub fn read_merged_cells(source: &str, worksheet: &str) -> Option<Vec<CellRange>> {
+    let mut archive = ZipArchive::new(BufReader::new(File::open(source).ok()?)).ok()?;
+
+    // the worksheet part is found from its relationship id in the workbook
+    let sheets = read_elements(&mut archive, "xl/workbook.xml", b"sheet")?;
+    let id = sheets
+        .iter()
+        .find(|sheet| sheet.get("name").map(String::as_str) == Some(worksheet))?
+        .get("id")?;
+    let relationships = read_elements(&mut archive, "xl/_rels/workbook.xml.rels", b"Relationship")?;
+    let target = relationships
+        .iter()
+        .find(|relationship| relationship.get("Id") == Some(id))?
+        .get("Target")?;
+    let path = match target.strip_prefix('/') {
+        Some(path) => path.to_string(),
+        None => format!("xl/{target}"),
+    };
+
+    let merges = read_elements(&mut archive, &path, b"mergeCell")?;
+    Some(
+        merges
+            .iter()
+            .filter_map(|merge| parse_range(merge.get("ref")?))
+            .collect(),
+    )
+}
+
+/// Return the attributes, by local name, of every `tag` element of a part of
+/// the archive
+fn read_elements(
+    archive: &mut Archive,
+    path: &str,
+    tag: &[u8],
+) -> Option<Vec<HashMap<String, String>>> {
+    let mut reader = Reader::from_reader(BufReader::new(archive.by_name(path).ok()?));
+    let mut buf = Vec::new();
+    let mut elements = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).ok()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == tag => {
+                let mut attributes = HashMap::new();
+                for attribute in e.attributes().flatten() {
+                    let key =
+                        String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string();
+                    let value = attribute
+                        .decode_and_unescape_value(&reader)
+                        .ok()?
+                        .to_string();
+                    attributes.insert(key, value);
+                }
+                elements.push(attributes);
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+    Some(elements)
+}
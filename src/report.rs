@@ -0,0 +1,241 @@
+use calamine::DataType;
+use serde::{Deserialize, Serialize};
+
+use crate::format::NumberFormat;
+use crate::sheet::CellAddress;
+use crate::summary::ErrorCell;
+
+/// The content of a pdf file, read from the sheets and written by the
+/// renderers: its products, their categories, and a row per parameter.
+/// It can also be read from a JSON or TOML data file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    #[serde(default)]
+    pub pdf_name: String,
+    pub products: Vec<ReportProduct>,
+}
+
+/// The page of a product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportProduct {
+    pub name: ReportCell,
+    /// Fields joined from the lookup sheet, like a description
+    #[serde(default)]
+    pub fields: Vec<ReportField>,
+    pub categories: Vec<ReportCategory>,
+}
+
+/// A field of a product, named by the header of its column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportField {
+    pub name: String,
+    pub value: ReportCell,
+}
+
+/// A category of a product, written as one table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportCategory {
+    pub title: ReportCell,
+    /// The labels of the parameters, like "Parameters" or "Unit"
+    pub headers: Vec<ReportCell>,
+    pub rows: Vec<ReportRow>,
+}
+
+/// A parameter of a category: a cell for every header, and the value of the
+/// product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRow {
+    /// The first level name of a two-level parameter header, the second level
+    /// name being the first cell of the row
+    #[serde(default)]
+    pub group: Option<String>,
+    pub parameters: Vec<ReportCell>,
+    pub value: ReportCell,
+    /// Whether the value is written highlighted, like a value changed by a
+    /// variant
+    #[serde(default)]
+    pub highlighted: bool,
+}
+
+/// A cell of the report: its formatted text, its value and where it was read.
+/// In a data file, a cell can be written as its value only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "ReportCellData")]
+pub struct ReportCell {
+    pub text: String,
+    pub value: CellValue,
+    pub address: Option<CellAddress>,
+}
+
+/// The forms of a cell in a data file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReportCellData {
+    Cell {
+        text: String,
+        #[serde(default)]
+        value: Option<CellValue>,
+        #[serde(default)]
+        address: Option<CellAddress>,
+    },
+    Text(String),
+    Integer(i64),
+    Number(f64),
+    Bool(bool),
+}
+
+impl From<ReportCellData> for ReportCell {
+    fn from(data: ReportCellData) -> Self {
+        let value = match data {
+            ReportCellData::Cell {
+                text,
+                value,
+                address,
+            } => {
+                return ReportCell {
+                    value: value.unwrap_or_else(|| CellValue::Text(text.to_string())),
+                    text,
+                    address,
+                }
+            }
+            ReportCellData::Text(text) => DataType::String(text),
+            ReportCellData::Integer(number) => DataType::Int(number),
+            ReportCellData::Number(number) => DataType::Float(number),
+            ReportCellData::Bool(value) => DataType::Bool(value),
+        };
+        ReportCell {
+            text: NumberFormat::default().format(&value),
+            value: CellValue::from(&value),
+            address: None,
+        }
+    }
+}
+
+/// The typed value of a cell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "value")]
+pub enum CellValue {
+    Empty,
+    Text(String),
+    Number(f64),
+    Integer(i64),
+    Bool(bool),
+    /// A date as an Excel serial number
+    Date(f64),
+    Error(String),
+}
+
+impl From<&DataType> for CellValue {
+    fn from(value: &DataType) -> Self {
+        match value {
+            DataType::Empty => CellValue::Empty,
+            DataType::String(text) => CellValue::Text(text.to_string()),
+            DataType::Float(number) => CellValue::Number(*number),
+            DataType::Int(number) => CellValue::Integer(*number),
+            DataType::Bool(value) => CellValue::Bool(*value),
+            DataType::DateTime(serial) => CellValue::Date(*serial),
+            DataType::Error(error) => CellValue::Error(error.to_string()),
+        }
+    }
+}
+
+impl From<&CellValue> for DataType {
+    fn from(value: &CellValue) -> Self {
+        match value {
+            CellValue::Empty => DataType::Empty,
+            CellValue::Text(text) => DataType::String(text.to_string()),
+            CellValue::Number(number) => DataType::Float(*number),
+            CellValue::Integer(number) => DataType::Int(*number),
+            CellValue::Bool(value) => DataType::Bool(*value),
+            CellValue::Date(serial) => DataType::DateTime(*serial),
+            // an error is written as its text
+            CellValue::Error(error) => DataType::String(error.to_string()),
+        }
+    }
+}
+
+impl ReportRow {
+    /// Return the name of the parameter of the row, after its group when it
+    /// has one, like "Tensile strength MD"
+    pub fn name(&self) -> String {
+        let name = self
+            .parameters
+            .first()
+            .map_or("", |cell| cell.text.as_str());
+        match &self.group {
+            Some(group) => format!("{group} {name}"),
+            None => name.to_string(),
+        }
+    }
+}
+
+impl ReportCategory {
+    /// Return the texts of the cells of the parameters, row by row
+    pub fn parameter_texts(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .flat_map(|row| row.parameters.iter().map(|cell| cell.text.to_string()))
+            .collect()
+    }
+
+    /// Return the index of the parameter column holding the units, the one
+    /// with the header "Unit"
+    pub fn unit_index(&self) -> Option<usize> {
+        self.headers
+            .iter()
+            .position(|header| header.text.trim().eq_ignore_ascii_case("unit"))
+    }
+}
+
+impl Report {
+    /// Return the names of the products, in the order of the pages
+    pub fn product_names(&self) -> Vec<String> {
+        self.products
+            .iter()
+            .map(|product| product.name.text.to_string())
+            .collect()
+    }
+
+    /// Return every cell of the report holding an Excel error and written in
+    /// the documents, a cell blanked by the error policy and the cells of a
+    /// row left out for its empty value being left out. A cell shown on
+    /// several pages is listed once.
+    pub fn error_cells(&self) -> Vec<ErrorCell> {
+        let mut output: Vec<ErrorCell> = Vec::new();
+        for product in &self.products {
+            let category_cells = product.categories.iter().flat_map(|category| {
+                let row_cells = category
+                    .rows
+                    .iter()
+                    .filter(|row| !row.value.text.is_empty())
+                    .flat_map(|row| row.parameters.iter().chain([&row.value]));
+                [&category.title]
+                    .into_iter()
+                    .chain(category.headers.iter())
+                    .chain(row_cells)
+            });
+            let field_cells = product.fields.iter().map(|field| &field.value);
+            let cells = [&product.name]
+                .into_iter()
+                .chain(field_cells)
+                .chain(category_cells);
+            for cell in cells.filter(|cell| !cell.text.is_empty()) {
+                if let (CellValue::Error(error), Some(address)) = (&cell.value, &cell.address) {
+                    let error_cell = ErrorCell {
+                        address: address.clone(),
+                        error: error.to_string(),
+                    };
+                    if !output.contains(&error_cell) {
+                        output.push(error_cell);
+                    }
+                }
+            }
+        }
+        output
+    }
+}
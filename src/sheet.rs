@@ -0,0 +1,573 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use calamine::{open_workbook_auto, CellErrorType, DataType, Range, Reader, Sheets};
+use serde::{Deserialize, Serialize};
+
+use crate::error::MissingLabel;
+use crate::matching::{Label, LabelMatcher, Region};
+use crate::merged_cells::read_merged_cells;
+use crate::{suggest, GradeError, Orientation};
+
+/// A rectangle of cells, from its first to its last (row, col) coordinates
+pub type CellRange = ((usize, usize), (usize, usize));
+
+/// Return the A1 name of a cell from its (row, col) coordinates
+pub fn cell_name((row, col): (usize, usize)) -> String {
+    let mut letters = String::new();
+    let mut col = col + 1;
+    while col > 0 {
+        letters.insert(0, (b'A' + ((col - 1) % 26) as u8) as char);
+        col = (col - 1) / 26;
+    }
+    format!("{}{}", letters, row + 1)
+}
+
+/// Return the (row, col) coordinates of a cell from its A1 name.
+/// The `$` of absolute references are ignored.
+pub fn parse_cell_name(name: &str) -> Option<(usize, usize)> {
+    let name = name.trim().replace('$', "");
+    let digits = name.find(|c: char| c.is_ascii_digit())?;
+    let (letters, row) = name.split_at(digits);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let col = letters
+        .to_ascii_uppercase()
+        .bytes()
+        .fold(0, |col, c| col * 26 + (c - b'A') as usize + 1);
+    let row: usize = row.parse().ok()?;
+    if row == 0 {
+        return None;
+    }
+    Some((row - 1, col - 1))
+}
+
+/// Split a reference like `'Sheet 1'!$A$1:$B$4` into its worksheet and its
+/// range of cells
+fn parse_reference(reference: &str) -> Option<(String, CellRange)> {
+    let (worksheet, cells) = reference.trim_start_matches('=').rsplit_once('!')?;
+    let worksheet = worksheet.trim_matches('\'').replace("''", "'");
+    Some((worksheet, parse_range(cells)?))
+}
+
+/// Return the cells of an A1 range like `A1:F10`, or of a single cell
+pub fn parse_range(cells: &str) -> Option<CellRange> {
+    let (start, end) = cells.split_once(':').unwrap_or((cells, cells));
+    Some((parse_cell_name(start)?, parse_cell_name(end)?))
+}
+
+/// An Excel table of a worksheet: the names of its columns and where its data
+/// is, without the header row
+#[derive(Debug, Clone)]
+struct SheetTable {
+    columns: Vec<String>,
+    data: Option<CellRange>,
+}
+
+/// Where a cell is: the workbook, the worksheet and the (row, col) coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CellAddress {
+    pub source: String,
+    pub worksheet: String,
+    pub cell: (usize, usize),
+}
+
+impl fmt::Display for CellAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?}[{:?}]!{}",
+            self.source,
+            self.worksheet,
+            cell_name(self.cell)
+        )
+    }
+}
+
+/// A worksheet loaded once in memory.
+/// It keeps the cells of the sheet and an index from the text of every
+/// non-empty cell to its coordinates, so labels can be found without scanning
+/// the whole sheet.
+/// A transposed sheet has its rows and columns swapped, the names, tables,
+/// merged cells and addresses keeping the coordinates of the worksheet.
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    source: String,
+    worksheet: String,
+    range: Range<DataType>,
+    labels: HashMap<String, Vec<(usize, usize)>>,
+    names: HashMap<String, CellRange>,
+    tables: HashMap<String, SheetTable>,
+    merged: Vec<CellRange>,
+    transposed: bool,
+}
+
+impl Sheet {
+    /// Open the workbook `source` and load the worksheet `worksheet`.
+    /// The format (xlsx, xlsm, xlsb, xls or ods) is found from the extension of
+    /// the file, or from its content when the extension is unknown.
+    /// Csv and tsv files are read as a single sheet, `worksheet` is then ignored.
+    pub fn load(source: &str, worksheet: &str) -> Result<Self, GradeError> {
+        match Path::new(source).extension().and_then(|e| e.to_str()) {
+            Some("csv") => return Self::load_csv(source, worksheet, b','),
+            Some("tsv") => return Self::load_csv(source, worksheet, b'\t'),
+            _ => (),
+        }
+        let workbook_error = |message: String| GradeError::Workbook {
+            source: source.to_string(),
+            message,
+        };
+        let mut workbook = open_workbook_auto(source).map_err(|e| workbook_error(e.to_string()))?;
+        let range = workbook
+            .worksheet_range(worksheet)
+            .ok_or_else(|| GradeError::Worksheet {
+                source: source.to_string(),
+                worksheet: worksheet.to_string(),
+                suggestions: suggest::closest(
+                    worksheet,
+                    workbook
+                        .sheet_names()
+                        .iter()
+                        .map(|name| (name.as_str(), ())),
+                )
+                .into_iter()
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            })?
+            .map_err(|e| workbook_error(e.to_string()))?;
+        let mut sheet = Self::from_range(source, worksheet, range);
+
+        // the defined names and the tables of the worksheet, to anchor labels
+        for (name, reference) in workbook.defined_names() {
+            if let Some((name_sheet, cells)) = parse_reference(reference) {
+                if name_sheet == worksheet {
+                    sheet.names.insert(name.to_string(), cells);
+                }
+            }
+        }
+        if let Sheets::Xlsx(xlsx) = &mut workbook {
+            sheet.merged = read_merged_cells(source, worksheet).unwrap_or_default();
+            xlsx.load_tables()
+                .map_err(|e| workbook_error(e.to_string()))?;
+            let table_names: Vec<String> = xlsx
+                .table_names_in_sheet(worksheet)
+                .into_iter()
+                .cloned()
+                .collect();
+            for name in table_names {
+                if let Some(table) = xlsx.table_by_name(&name) {
+                    let table = table.map_err(|e| workbook_error(e.to_string()))?;
+                    let to_usize = |(row, col): (u32, u32)| (row as usize, col as usize);
+                    let data = table
+                        .data()
+                        .start()
+                        .zip(table.data().end())
+                        .map(|(start, end)| (to_usize(start), to_usize(end)));
+                    sheet.tables.insert(
+                        name,
+                        SheetTable {
+                            columns: table.columns().to_vec(),
+                            data,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(sheet)
+    }
+
+    /// Load a delimited text file in the same grid as a worksheet.
+    /// Numbers are read as floats, like calamine does for the workbooks.
+    pub fn load_csv(source: &str, worksheet: &str, delimiter: u8) -> Result<Self, GradeError> {
+        let workbook_error = |e: csv::Error| GradeError::Workbook {
+            source: source.to_string(),
+            message: e.to_string(),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_path(source)
+            .map_err(workbook_error)?;
+        let mut rows: Vec<Vec<DataType>> = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(workbook_error)?;
+            rows.push(record.iter().map(parse_csv_field).collect());
+        }
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if rows.is_empty() || width == 0 {
+            return Ok(Self::from_range(source, worksheet, Range::empty()));
+        }
+        let mut range = Range::new((0, 0), (rows.len() as u32 - 1, width as u32 - 1));
+        for (row, values) in rows.into_iter().enumerate() {
+            for (col, value) in values.into_iter().enumerate() {
+                range.set_value((row as u32, col as u32), value);
+            }
+        }
+        Ok(Self::from_range(source, worksheet, range))
+    }
+
+    /// Build the sheet model from an already loaded range.
+    pub fn from_range(source: &str, worksheet: &str, range: Range<DataType>) -> Self {
+        let (start_row, start_col) = range.start().unwrap_or((0, 0));
+        let mut labels: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+        for (row, col, value) in range.used_cells() {
+            labels
+                .entry(value.to_string())
+                .or_default()
+                .push((row + start_row as usize, col + start_col as usize));
+        }
+        Self {
+            source: source.to_string(),
+            worksheet: worksheet.to_string(),
+            range,
+            labels,
+            names: HashMap::new(),
+            tables: HashMap::new(),
+            merged: Vec::new(),
+            transposed: false,
+        }
+    }
+
+    /// Return the sheet with its rows and columns swapped, for the worksheets
+    /// with a product per column
+    pub fn transpose(&self) -> Self {
+        let swap = |(row, col): (u32, u32)| (col, row);
+        let mut range = match (self.range.start(), self.range.end()) {
+            (Some(start), Some(end)) => Range::new(swap(start), swap(end)),
+            _ => Range::empty(),
+        };
+        let (start_row, start_col) = self.range.start().unwrap_or((0, 0));
+        for (row, col, value) in self.range.used_cells() {
+            range.set_value(
+                swap((row as u32 + start_row, col as u32 + start_col)),
+                value.clone(),
+            );
+        }
+        Self {
+            names: self.names.clone(),
+            tables: self.tables.clone(),
+            merged: self.merged.clone(),
+            transposed: !self.transposed,
+            ..Self::from_range(&self.source, &self.worksheet, range)
+        }
+    }
+
+    /// Convert coordinates of the worksheet into coordinates of the sheet,
+    /// or the reverse
+    fn orient(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        match self.transposed {
+            true => (col, row),
+            false => (row, col),
+        }
+    }
+
+    fn orient_range(&self, (start, end): CellRange) -> CellRange {
+        (self.orient(start), self.orient(end))
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn worksheet(&self) -> &str {
+        &self.worksheet
+    }
+
+    /// Return the value at the absolute coordinates (row, col)
+    pub fn get_value(&self, (row, col): (usize, usize)) -> Option<&DataType> {
+        self.range.get_value((row as u32, col as u32))
+    }
+
+    /// Return the value at the coordinates, or an error if the cell is outside
+    /// of the sheet
+    pub fn get_cell(&self, cell: (usize, usize)) -> Result<&DataType, GradeError> {
+        self.get_value(cell)
+            .ok_or_else(|| GradeError::MissingCell(self.address(cell)))
+    }
+
+    /// Return the last cell of a category header: the end of the merged cells
+    /// starting at the header, or else the cell before the next non-empty
+    /// cell on its right
+    pub fn category_end(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        let mut merged = self.merged.iter().map(|cells| self.orient_range(*cells));
+        if let Some((_, (_, end_col))) = merged.find(|(start, _)| *start == (row, col)) {
+            return (row, end_col);
+        }
+        let last_col = self
+            .range
+            .end()
+            .map_or(col, |(_, end_col)| end_col as usize);
+        let mut end = col;
+        while end < last_col && self.get_value((row, end + 1)) == Some(&DataType::Empty) {
+            end += 1;
+        }
+        (row, end)
+    }
+
+    /// Return the block of a category: the columns under its header, down to
+    /// the last row of the sheet, or nothing when the category is not in the
+    /// sheet. The category must not be found several times.
+    pub fn category_block(
+        &self,
+        category: &str,
+        matcher: &LabelMatcher,
+    ) -> Result<Option<CellRange>, GradeError> {
+        match self.search(matcher)?[..] {
+            [] => Ok(None),
+            [cell] => {
+                let (_, end_col) = self.category_end(cell);
+                let last_row = self.range.end().map_or(cell.0, |(row, _)| row as usize);
+                Ok(Some((cell, (last_row, end_col))))
+            }
+            ref cells => Err(GradeError::AmbiguousScope {
+                category: category.to_string(),
+                cells: cells.iter().map(|cell| self.address(*cell)).collect(),
+            }),
+        }
+    }
+
+    /// Return the non-empty cells of a row, from left to right
+    pub fn row_cells(&self, row: usize) -> Vec<(usize, usize)> {
+        match (self.range.start(), self.range.end()) {
+            (Some((_, start_col)), Some((_, end_col))) => {
+                self.non_empty_cells(((row, start_col as usize), (row, end_col as usize)))
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Return the non-empty cells of a column, from top to bottom
+    pub fn column_cells(&self, col: usize) -> Vec<(usize, usize)> {
+        match (self.range.start(), self.range.end()) {
+            (Some((start_row, _)), Some((end_row, _))) => {
+                self.non_empty_cells(((start_row as usize, col), (end_row as usize, col)))
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Return the full address of a cell of the sheet
+    pub fn address(&self, cell: (usize, usize)) -> CellAddress {
+        CellAddress {
+            source: self.source.clone(),
+            worksheet: self.worksheet.clone(),
+            cell: self.orient(cell),
+        }
+    }
+
+    /// Return the size (rows, columns) of the sheet
+    pub fn get_size(&self) -> (usize, usize) {
+        self.range.get_size()
+    }
+
+    /// Return the label with the closest texts found in the sheets.
+    /// Nothing is suggested for anchors.
+    pub fn suggest(label: &Label, sheets: &[Rc<Sheet>]) -> MissingLabel {
+        let target = match label {
+            Label::Text(text) | Label::Scoped { label: text, .. } => text,
+            _ => {
+                return MissingLabel {
+                    label: label.to_string(),
+                    suggestions: Vec::new(),
+                }
+            }
+        };
+        let candidates = sheets.iter().flat_map(|sheet| {
+            sheet
+                .labels
+                .iter()
+                .map(move |(text, cells)| (text.as_str(), (sheet, cells)))
+        });
+        MissingLabel {
+            label: label.to_string(),
+            suggestions: suggest::closest(target, candidates)
+                .into_iter()
+                .map(|(text, (sheet, cells))| {
+                    (
+                        text.to_string(),
+                        cells.iter().map(|cell| sheet.address(*cell)).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Return every coordinates where the cell text is exactly `label`
+    pub fn find(&self, label: &str) -> &[(usize, usize)] {
+        self.labels.get(label).map(|v| &v[..]).unwrap_or(&[])
+    }
+
+    /// Return every coordinates where the cell text matches the label, in the
+    /// order of the sheet. It fails when the category of a scoped label is
+    /// found several times.
+    pub fn search(&self, matcher: &LabelMatcher) -> Result<Vec<(usize, usize)>, GradeError> {
+        let found = match matcher {
+            LabelMatcher::Exact(label) => self.find(label).to_vec(),
+            LabelMatcher::Cell { worksheet, cell } => {
+                if worksheet.as_ref().is_none_or(|w| *w == self.worksheet) {
+                    let cell = self.orient(*cell);
+                    self.non_empty_cells((cell, cell))
+                } else {
+                    Vec::new()
+                }
+            }
+            LabelMatcher::Name(name) => match self.names.get(name) {
+                Some(cells) => self.non_empty_cells(self.orient_range(*cells)),
+                None => Vec::new(),
+            },
+            LabelMatcher::Table { table, column } => {
+                let table = match self.tables.get(table) {
+                    Some(table) => table,
+                    None => return Ok(Vec::new()),
+                };
+                let id_col = match column {
+                    Some(column) => table.columns.iter().position(|c| c == column),
+                    None => Some(0),
+                };
+                match (id_col, table.data) {
+                    (Some(id_col), Some(((start_row, start_col), (end_row, _)))) => {
+                        let col = start_col + id_col;
+                        self.non_empty_cells(self.orient_range(((start_row, col), (end_row, col))))
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            LabelMatcher::Scoped { matcher, regions } => {
+                let mut cell_ranges: Vec<CellRange> = Vec::new();
+                for region in regions {
+                    match region {
+                        Region::Range(cells) => cell_ranges.push(self.orient_range(*cells)),
+                        Region::Within { category, matcher } => {
+                            match self.category_block(category, matcher)? {
+                                Some(cells) => cell_ranges.push(cells),
+                                None => return Ok(Vec::new()),
+                            }
+                        }
+                    }
+                }
+                let is_inside = |(row, col): &(usize, usize)| {
+                    cell_ranges
+                        .iter()
+                        .all(|((start_row, start_col), (end_row, end_col))| {
+                            (start_row..=end_row).contains(&row)
+                                && (start_col..=end_col).contains(&col)
+                        })
+                };
+                self.search(matcher)?
+                    .into_iter()
+                    .filter(is_inside)
+                    .collect()
+            }
+            _ => {
+                let mut found: Vec<(usize, usize)> = self
+                    .labels
+                    .iter()
+                    .filter(|(text, _)| matcher.is_match(text))
+                    .flat_map(|(_, cells)| cells.iter().copied())
+                    .collect();
+                found.sort_unstable();
+                found
+            }
+        };
+        Ok(found)
+    }
+
+    /// Return the non-empty cells of a rectangle of the sheet, row by row
+    fn non_empty_cells(
+        &self,
+        ((start_row, start_col), (end_row, end_col)): CellRange,
+    ) -> Vec<(usize, usize)> {
+        let mut cells: Vec<(usize, usize)> = Vec::new();
+        for row in start_row..=end_row {
+            for col in start_col..=end_col {
+                match self.get_value((row, col)) {
+                    Some(DataType::Empty) | None => (),
+                    Some(_) => cells.push((row, col)),
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// Convert a csv field into a cell value
+fn parse_csv_field(field: &str) -> DataType {
+    if field.is_empty() {
+        return DataType::Empty;
+    }
+    // the errors of the formulas are exported as their text
+    let error = match field.trim() {
+        "#DIV/0!" => Some(CellErrorType::Div0),
+        "#N/A" => Some(CellErrorType::NA),
+        "#NAME?" => Some(CellErrorType::Name),
+        "#NULL!" => Some(CellErrorType::Null),
+        "#NUM!" => Some(CellErrorType::Num),
+        "#REF!" => Some(CellErrorType::Ref),
+        "#VALUE!" => Some(CellErrorType::Value),
+        _ => None,
+    };
+    if let Some(error) = error {
+        return DataType::Error(error);
+    }
+    match field.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() => DataType::Float(value),
+        _ => DataType::String(field.to_string()),
+    }
+}
+
+/// Cache of the loaded sheets, shared by every pdf file of a config.
+/// A sheet is loaded only the first time a `source`/`worksheet` pair is asked.
+#[derive(Debug, Default)]
+pub struct SheetCache {
+    sheets: HashMap<(String, String), Rc<Sheet>>,
+    transposed: HashMap<(String, String), Rc<Sheet>>,
+}
+
+impl SheetCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the sheet, loading it if it is not in the cache yet
+    pub fn get(&mut self, source: &str, worksheet: &str) -> Result<Rc<Sheet>, GradeError> {
+        let key = (source.to_string(), worksheet.to_string());
+        if let Some(sheet) = self.sheets.get(&key) {
+            return Ok(Rc::clone(sheet));
+        }
+        let sheet = Rc::new(Sheet::load(source, worksheet)?);
+        self.sheets.insert(key, Rc::clone(&sheet));
+        Ok(sheet)
+    }
+
+    /// Return the sheet read with an orientation, transposed when the products
+    /// are columns
+    pub fn get_oriented(
+        &mut self,
+        source: &str,
+        worksheet: &str,
+        orientation: Orientation,
+    ) -> Result<Rc<Sheet>, GradeError> {
+        if orientation == Orientation::Rows {
+            return self.get(source, worksheet);
+        }
+        let key = (source.to_string(), worksheet.to_string());
+        if let Some(sheet) = self.transposed.get(&key) {
+            return Ok(Rc::clone(sheet));
+        }
+        let sheet = Rc::new(self.get(source, worksheet)?.transpose());
+        self.transposed.insert(key, Rc::clone(&sheet));
+        Ok(sheet)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sheets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sheets.is_empty()
+    }
+}
@@ -0,0 +1,46 @@
+/// Maximum number of suggestions given for one text
+const NB_SUGGESTIONS: usize = 3;
+
+/// Normalize a text before comparing it: lower case and without any whitespace
+fn normalize(text: &str) -> Vec<char> {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance between two texts
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Return the candidates closest to `target` by edit distance, ignoring case
+/// and whitespaces, best first. Candidates too different are left out.
+pub fn closest<'a, T>(
+    target: &str,
+    candidates: impl Iterator<Item = (&'a str, T)>,
+) -> Vec<(&'a str, T)> {
+    let target = normalize(target);
+    let max_distance = (target.len() / 3).max(2);
+    let mut found: Vec<(usize, &'a str, T)> = candidates
+        .filter_map(|(text, value)| {
+            let d = distance(&target, &normalize(text));
+            (d <= max_distance).then_some((d, text, value))
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(b.1)));
+    found
+        .into_iter()
+        .take(NB_SUGGESTIONS)
+        .map(|(_, text, value)| (text, value))
+        .collect()
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+use crate::sheet::CellAddress;
+
+/// A cell holding an Excel error, like `#N/A` or `#DIV/0!`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorCell {
+    pub address: CellAddress,
+    pub error: String,
+}
+
+impl fmt::Display for ErrorCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.address, self.error)
+    }
+}
+
+/// What a run published, printed at its end
+#[derive(Debug, Default)]
+pub struct RunSummary {
+    /// Names of the pdf files created
+    pub documents: Vec<String>,
+    /// Error cells written in the created pdf files, with the pdf name
+    pub error_cells: Vec<(String, ErrorCell)>,
+}
+
+impl RunSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a created pdf file and the error cells written in it. A cell
+    /// shared by several pages of the pdf is listed once.
+    pub fn add_document(&mut self, pdf_name: &str, error_cells: &[ErrorCell]) {
+        self.documents.push(pdf_name.to_string());
+        for cell in error_cells {
+            let entry = (pdf_name.to_string(), cell.clone());
+            if !self.error_cells.contains(&entry) {
+                self.error_cells.push(entry);
+            }
+        }
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} pdf created, {} error cells published",
+            self.documents.len(),
+            self.error_cells.len()
+        )?;
+        for (pdf_name, cell) in &self.error_cells {
+            write!(f, "\n    {pdf_name:?}: {cell}")?;
+        }
+        Ok(())
+    }
+}
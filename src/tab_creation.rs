@@ -1,4 +1,4 @@
-use crate::AlignTab;
+use crate::{AlignTab, ParameterGroup};
 
 use latex::{Document, Element};
 /// Define how shoud be aligne the columns
@@ -34,7 +34,7 @@ pub fn add_empty_rows(content: &mut String, nb_rows: usize, nb_params: usize) ->
 
 /// Create the title of a tabular, very specific
 pub fn create_title_tabularx(title: String, nb_col: usize) -> String {
-    let mut title = String::from(format!("\\rowcolor{{color_title}}{}", title));
+    let mut title = format!("\\rowcolor{{color_title}}{}", title);
     add_empty_rows(&mut title, nb_col, 1);
     end_line_tab(&mut title)
 }
@@ -52,7 +52,7 @@ pub fn reshape_vector_by_col(single_shape_vec: Vec<String>, nb_param: usize) ->
     for i in 0..(single_shape_vec.len() / (single_shape_vec.len() / nb_param)) {
         let mut buff_vec: Vec<String> = Vec::new();
         for j in (i..single_shape_vec.len()).step_by(nb_param) {
-            buff_vec.push(single_shape_vec.iter().nth(j).unwrap().to_string());
+            buff_vec.push(single_shape_vec[j].to_string());
         }
         reshaped_param.push(buff_vec);
     }
@@ -68,11 +68,11 @@ pub fn transpose2dvec(col_vec: Vec<Vec<String>>) -> Vec<Vec<String>> {
     // let test = &col_vec[..];
     let mut output: Vec<Vec<String>> = Vec::new();
     for i in 0..size_row {
-        let mut buff_vec: Vec<String> = Vec::new();
-        for j in 0..size_col {
-            buff_vec.push(col_vec[..][j][i].to_string());
-        }
-        if buff_vec.iter().nth(1) != Some(&String::from("")) {
+        let buff_vec: Vec<String> = col_vec[..size_col]
+            .iter()
+            .map(|col| col[i].to_string())
+            .collect();
+        if buff_vec.get(1) != Some(&String::from("")) {
             output.push(buff_vec);
         }
     }
@@ -100,36 +100,90 @@ pub fn clean_vector(double_shape_vec: Vec<Vec<String>>) -> (Vec<Vec<String>>, Ve
 /// Function that call all the cleaning function of the data
 /// The main goal is not to have any empty row
 /// n/a is not considered as an empty row
+/// The grouped parameters are named by their second level name, and the
+/// group of each row is kept as its last element.
 pub fn clean_content(
-    parameters: &Vec<String>,
-    content: &Vec<String>,
+    parameters: &[String],
+    content: &[String],
     nb_param: usize,
-) -> (Vec<Vec<String>>, Vec<usize>) {
-    assert_eq!(parameters.len() % nb_param, 0);
+    groups: &[Option<ParameterGroup>],
+) -> Result<(Vec<Vec<String>>, Vec<usize>), String> {
+    if nb_param == 0 {
+        return Err(String::from("the category has no parameter headers"));
+    }
+    if content.is_empty() {
+        return Err(String::from("the category has no rows"));
+    }
+    if parameters.len() != nb_param * content.len() {
+        return Err(format!(
+            "the category has {nb_param} headers, but {} parameter cells for {} rows",
+            parameters.len(),
+            content.len()
+        ));
+    }
     // Cleaning and re organizing the data
     let parameters = reshape_vector_by_col(parameters.to_vec(), nb_param);
     let (mut clean_param, useless_col) = clean_vector(parameters);
+    // the first column names the rows
+    if useless_col.first() == Some(&0) {
+        return Err(String::from("the parameters of the category have no names"));
+    }
+    let mut group_names: Vec<String> = vec![String::new(); content.len()];
+    for (i, group) in groups.iter().enumerate() {
+        if let (Some((group, name)), Some(names)) = (group, clean_param.first_mut()) {
+            names[i] = name.to_string();
+            group_names[i] = group.to_string();
+        }
+    }
     clean_param.insert(1, content.to_vec());
-    (transpose2dvec(clean_param), useless_col)
+    clean_param.push(group_names);
+    Ok((transpose2dvec(clean_param), useless_col))
+}
+
+/// Put a heading row spanning the `nb_col` columns of the tabular before the
+/// first row of each group of parameters. The group of a row is taken from its
+/// last element.
+pub fn add_group_headings(content: Vec<Vec<String>>, nb_col: usize) -> Vec<Vec<String>> {
+    let mut output: Vec<Vec<String>> = Vec::new();
+    let mut current_group = String::new();
+    for mut line in content {
+        let group = line.pop().unwrap_or_default();
+        if !group.is_empty() && group != current_group {
+            output.push(vec![format!(
+                "\\multicolumn{{{}}}{{l}}{{\\textbf{{{}}}}}",
+                nb_col, group
+            )]);
+        }
+        current_group = group;
+        output.push(line);
+    }
+    output
+}
+
+/// Number of columns taken by the cells of a line, a multicolumn taking
+/// several of them
+pub fn count_columns(line: &[String]) -> usize {
+    line.iter()
+        .map(|cell| {
+            cell.strip_prefix("\\multicolumn{")
+                .and_then(|rest| rest.split('}').next())
+                .and_then(|nb_col| nb_col.parse().ok())
+                .unwrap_or(1)
+        })
+        .sum()
 }
 
 /// Function to create the content of the tab
-/// Must take a clean content to properly work
+/// Must take a clean content to properly work, with the texts of the cells
+/// already escaped
 pub fn create_content(clean_content: Vec<Vec<String>>, nb_col: usize) -> String {
     let mut content: String = String::new();
     for line in clean_content.iter() {
         content.push_str(&line.join(" & "));
-        add_empty_rows(&mut content, nb_col, line.len());
+        add_empty_rows(&mut content, nb_col, count_columns(line));
         end_line_tab(&mut content);
         content.push_str(&add_colored_line());
     }
-    // just for now, to be improved later
-    content = content.replace("%", "\\%");
-    content = content.replace("μ", "\\(\\mu\\)");
-    content = content.replace("µ", "\\(\\mu\\)");
-    content = content.replace("<", "\\(<\\) ");
-    content = content.replace(">", "\\(>\\) ");
-    content = content.replace("m2", "\\(m^2\\)");
     content
 }
 
@@ -137,16 +191,14 @@ pub fn create_content(clean_content: Vec<Vec<String>>, nb_col: usize) -> String
 /// here for the parameters names).
 pub fn create_parameters_tabularx(
     parameters: &mut Vec<String>,
-    useless_col: &Vec<usize>,
+    useless_col: &[usize],
     nb_col: usize,
 ) -> String {
     parameters
         .iter_mut()
         .for_each(|f| *f = format!("\\textbf{{{}}}", f));
-    let mut j = 0;
-    for i in useless_col.iter() {
+    for (j, i) in useless_col.iter().enumerate() {
         parameters.remove(*i - j);
-        j += 1;
     }
 
     parameters.insert(1, String::from("\\textbf{Target Value}"));
@@ -162,57 +214,56 @@ pub fn create_parameters_tabularx(
 /// can be added.
 pub fn define_environment(name: String, parameters: String, content: String) -> String {
     if parameters.is_empty() {
-        return format!("\\begin{{{name}}}\n{content}\n\\end{{{name}}}");
+        format!("\\begin{{{name}}}\n{content}\n\\end{{{name}}}")
     } else {
-        return format!("\\begin{{{name}}}{{{parameters}}}\n{content}\n\\end{{{name}}}");
+        format!("\\begin{{{name}}}{{{parameters}}}\n{content}\n\\end{{{name}}}")
     }
 }
 
-pub fn find_larger_rows(content: &Vec<Vec<String>>) -> Vec<usize> {
+pub fn find_larger_rows(content: &[Vec<String>]) -> Vec<usize> {
     let mut indices_bigger_row: Vec<usize> = Vec::new();
     content.iter().enumerate().for_each(|(i, e)| {
-        if e.first().unwrap().len() > 26 {
+        let first = e.first().unwrap();
+        if first.len() > 26 && !first.starts_with("\\multicolumn") {
             indices_bigger_row.push(i)
         }
     });
     indices_bigger_row
 }
 
-pub fn add_rule_row(content: &mut Vec<Vec<String>>, indices: Vec<usize>) {
+pub fn add_rule_row(content: &mut [Vec<String>], indices: Vec<usize>) {
     for (i, value) in content.iter_mut().enumerate() {
-        if indices.iter().find(|v| **v == i).is_some() {
-            value
-                .iter_mut()
-                .nth(0)
-                .unwrap()
-                .push_str(" \\rule{80pt}{0pt}");
+        if indices.contains(&i) {
+            value.first_mut().unwrap().push_str(" \\rule{80pt}{0pt}");
         }
     }
 }
 
 /// Function that reunite all the tabular creation functions
-/// add to the page one centered tabular
+/// add to the page one centered tabular. The tabular has a column per
+/// parameter column which is not empty, and one for the target value. It
+/// fails when the parameters do not fill the rows of the headers.
+#[allow(clippy::too_many_arguments)]
 pub fn create_tabularx(
     page: &mut Document,
-    nb_col: usize,
     title: &String,
     parameters: &mut Vec<String>,
-    general_content: &Vec<String>,
-    product_values: &Vec<String>,
+    general_content: &[String],
+    product_values: &[String],
     nb_param: usize,
+    groups: &[Option<ParameterGroup>],
     align: &AlignTab,
-) {
-    let (mut cleaned_content, useless_col) =
-        clean_content(general_content, product_values, nb_param);
-    let two_col_tab: bool = match cleaned_content.len() {
-        0..=13 => false,
-        _ => true,
-    };
+) -> Result<(), String> {
+    let (cleaned_content, useless_col) =
+        clean_content(general_content, product_values, nb_param, groups)?;
+    let nb_col = nb_param - useless_col.len() + 1;
+    let mut cleaned_content = add_group_headings(cleaned_content, nb_col);
+    let two_col_tab: bool = !matches!(cleaned_content.len(), 0..=13);
     // textwidth change
     let mut tabular_content: Vec<String> = Vec::new();
-    let title = vec![
+    let title = [
         "{\\textwidth}".to_string(),
-        format!("{{{}}}", define_column(nb_col, &align)),
+        format!("{{{}}}", define_column(nb_col, align)),
         create_title_tabularx(title.to_string(), nb_col),
     ];
 
@@ -230,13 +281,13 @@ pub fn create_tabularx(
         let title_in_env =
             define_environment("tabularx".to_string(), "".to_string(), title.join(""));
 
-        let content_1st_half = vec![
-            format!("{{{}}}", define_column(nb_col, &align)),
+        let content_1st_half = [
+            format!("{{{}}}", define_column(nb_col, align)),
             params.clone(),
             create_content(first_half, nb_col),
         ];
-        let content_2nd_half = vec![
-            format!("{{{}}}", define_column(nb_col, &align)),
+        let content_2nd_half = [
+            format!("{{{}}}", define_column(nb_col, align)),
             params.clone(),
             create_content(cleaned_content, nb_col),
         ];
@@ -254,12 +305,12 @@ pub fn create_tabularx(
 
         let switch_col = String::from("\\switchcolumn");
         tabular_content.push(
-            vec![
+            [
                 title_in_env,
                 define_environment(
                     "paracol".to_string(),
                     "2".to_string(),
-                    vec![left_tab, switch_col, right_tab].join(""),
+                    [left_tab, switch_col, right_tab].join(""),
                 ),
             ]
             .join(""),
@@ -278,4 +329,5 @@ pub fn create_tabularx(
     let tab = Element::Environment(String::from("center"), tabular_content);
 
     page.push(tab);
+    Ok(())
 }
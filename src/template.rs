@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+/// Delimiters of the tags of a template, chosen not to clash with the braces
+/// of LaTeX
+const OPEN_TAG: &str = "<<";
+const CLOSE_TAG: &str = ">>";
+
+/// A value given to a template: a text, or a list of scopes for a loop
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue {
+    Text(String),
+    List(Vec<TemplateScope>),
+}
+
+/// The values of a template by name
+pub type TemplateScope = HashMap<String, TemplateValue>;
+
+/// A part of a parsed template
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Placeholder(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+/// A section being parsed: its name and whether it is inverted, none for the
+/// root of the template
+type OpenSection = Option<(String, bool)>;
+
+/// Fill a template with the values of a scope.
+///
+/// `<<name>>` is replaced by the text `name`. `<<#name>>...<</name>>` is
+/// written once for every item of the list `name`, with the values of the item
+/// added to the scope, or once when `name` is a non-empty text.
+/// `<<^name>>...<</name>>` is written only when `name` is empty or missing.
+/// A placeholder missing from the scope is an error, a missing section is
+/// empty.
+pub fn render_template(template: &str, scope: &TemplateScope) -> Result<String, String> {
+    let nodes = parse(template)?;
+    let mut output = String::new();
+    render_nodes(&nodes, &mut vec![scope], &mut output)?;
+    Ok(output)
+}
+
+/// Parse a template into its nodes, checking that every section is closed
+fn parse(template: &str) -> Result<Vec<Node>, String> {
+    // the stack of the open sections, with the nodes found in each
+    let mut stack: Vec<(OpenSection, Vec<Node>)> = vec![(None, Vec::new())];
+    let mut rest = template;
+    while let Some(start) = rest.find(OPEN_TAG) {
+        let end = rest[start..]
+            .find(CLOSE_TAG)
+            .map(|end| start + end)
+            .ok_or_else(|| format!("unclosed tag {:?}", &rest[start..]))?;
+        let nodes = &mut stack.last_mut().expect("the root is never closed").1;
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let tag = rest[start + OPEN_TAG.len()..end].trim();
+        rest = &rest[end + CLOSE_TAG.len()..];
+        if let Some(name) = tag.strip_prefix('#') {
+            stack.push((Some((name.trim().to_string(), false)), Vec::new()));
+        } else if let Some(name) = tag.strip_prefix('^') {
+            stack.push((Some((name.trim().to_string(), true)), Vec::new()));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let (open, children) = stack.pop().expect("the root is never closed");
+            match open {
+                Some((open_name, inverted)) if open_name == name.trim() => stack
+                    .last_mut()
+                    .ok_or_else(|| format!("<</{open_name}>> is never opened"))?
+                    .1
+                    .push(Node::Section {
+                        name: open_name,
+                        inverted,
+                        children,
+                    }),
+                Some((open_name, _)) => {
+                    return Err(format!(
+                        "<<#{open_name}>> is closed by <</{}>>",
+                        name.trim()
+                    ))
+                }
+                None => return Err(format!("<</{}>> is never opened", name.trim())),
+            }
+        } else {
+            nodes.push(Node::Placeholder(tag.to_string()));
+        }
+    }
+    let (open, mut nodes) = stack.pop().expect("the root is never closed");
+    if let Some((name, _)) = open {
+        return Err(format!("<<#{name}>> is never closed"));
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    Ok(nodes)
+}
+
+/// Return the value of a name in the innermost scope holding it
+fn lookup<'a>(scopes: &[&'a TemplateScope], name: &str) -> Option<&'a TemplateValue> {
+    scopes.iter().rev().find_map(|scope| scope.get(name))
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    scopes: &mut Vec<&'a TemplateScope>,
+    output: &mut String,
+) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Placeholder(name) => match lookup(scopes, name) {
+                Some(TemplateValue::Text(text)) => output.push_str(text),
+                Some(TemplateValue::List(_)) => {
+                    return Err(format!("<<{name}>> is a list, written with <<#{name}>>"))
+                }
+                None => return Err(format!("unknown placeholder <<{name}>>")),
+            },
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let value = lookup(scopes, name);
+                let is_empty = match value {
+                    Some(TemplateValue::Text(text)) => text.is_empty(),
+                    Some(TemplateValue::List(items)) => items.is_empty(),
+                    None => true,
+                };
+                match (value, inverted) {
+                    (_, true) if is_empty => render_nodes(children, scopes, output)?,
+                    (Some(TemplateValue::List(items)), false) => {
+                        for item in items {
+                            scopes.push(item);
+                            render_nodes(children, scopes, output)?;
+                            scopes.pop();
+                        }
+                    }
+                    (Some(TemplateValue::Text(_)), false) if !is_empty => {
+                        render_nodes(children, scopes, output)?
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::escape::escape_latex;
+use crate::report::ReportRow;
+
+/// siunitx macros of the units, by symbol
+const UNITS: [(&str, &str); 22] = [
+    ("°C", "\\degreeCelsius"),
+    ("°", "\\degree"),
+    ("%", "\\percent"),
+    ("g", "\\gram"),
+    ("m", "\\metre"),
+    ("s", "\\second"),
+    ("min", "\\minute"),
+    ("h", "\\hour"),
+    ("N", "\\newton"),
+    ("Pa", "\\pascal"),
+    ("bar", "\\bar"),
+    ("J", "\\joule"),
+    ("W", "\\watt"),
+    ("K", "\\kelvin"),
+    ("l", "\\litre"),
+    ("L", "\\litre"),
+    ("mol", "\\mole"),
+    ("V", "\\volt"),
+    ("A", "\\ampere"),
+    ("Hz", "\\hertz"),
+    ("cd", "\\candela"),
+    ("Ω", "\\ohm"),
+];
+
+/// siunitx macros of the prefixes, by symbol. "K" is the kilo often written
+/// in "KJ".
+const PREFIXES: [(&str, &str); 12] = [
+    ("G", "\\giga"),
+    ("M", "\\mega"),
+    ("k", "\\kilo"),
+    ("K", "\\kilo"),
+    ("h", "\\hecto"),
+    ("d", "\\deci"),
+    ("c", "\\centi"),
+    ("m", "\\milli"),
+    ("µ", "\\micro"),
+    ("μ", "\\micro"),
+    ("u", "\\micro"),
+    ("n", "\\nano"),
+];
+
+/// A value with a tolerance, like "1.25 ± 0.02" or "20 +/- 2"
+fn tolerance() -> &'static Regex {
+    static TOLERANCE: OnceLock<Regex> = OnceLock::new();
+    TOLERANCE.get_or_init(|| {
+        Regex::new(r"^\s*(-?\d+(?:[.,]\d+)?)\s*(?:±|\+/-|\+-)\s*(\d+(?:[.,]\d+)?)\s*$")
+            .expect("valid regex")
+    })
+}
+
+/// Write the text of a unit cell, like "N/mm2" or "°C", as a siunitx unit.
+/// A text which is not a unit, like "weight %", is only escaped.
+pub fn typeset_unit(text: &str) -> String {
+    match parse_unit(text) {
+        Some(unit) => format!("\\si{{{unit}}}"),
+        None => escape_latex(text),
+    }
+}
+
+/// Write a value with a tolerance as a siunitx number, keeping its decimal
+/// marker. Any other value is only escaped.
+pub fn typeset_value(text: &str) -> String {
+    let captures = match tolerance().captures(text) {
+        Some(captures) => captures,
+        None => return escape_latex(text),
+    };
+    let number = format!("{} +- {}", &captures[1], &captures[2]);
+    match text.contains(',') {
+        true => format!("\\num[output-decimal-marker={{,}}]{{{number}}}"),
+        false => format!("\\num{{{number}}}"),
+    }
+}
+
+/// Return the LaTeX texts of the parameters of a row and of its value. The
+/// cell in the unit column is typeset as a unit, and the value of a row with
+/// a unit as a quantity; every other text is only escaped.
+pub(crate) fn typeset_row(row: &ReportRow, unit_index: Option<usize>) -> (Vec<String>, String) {
+    let parameters: Vec<String> = row
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| match Some(i) == unit_index {
+            true => typeset_unit(&cell.text),
+            false => escape_latex(&cell.text),
+        })
+        .collect();
+    let has_unit = unit_index
+        .and_then(|i| row.parameters.get(i))
+        .is_some_and(|cell| !cell.text.trim().is_empty());
+    let value = match has_unit {
+        true => typeset_value(&row.value.text),
+        false => escape_latex(&row.value.text),
+    };
+    (parameters, value)
+}
+
+/// Return the siunitx macros of a unit: units separated by spaces, "·" or
+/// "*", and "/" before a unit in the denominator
+fn parse_unit(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut output = String::new();
+    let mut per = false;
+    let mut term = String::new();
+    for c in text.chars().chain([' ']) {
+        match c {
+            ' ' | '·' | '*' | '/' => {
+                if !term.is_empty() {
+                    if per {
+                        output.push_str("\\per");
+                    }
+                    output.push_str(&parse_term(&term)?);
+                    term.clear();
+                    per = false;
+                }
+                if c == '/' {
+                    // a "/" must be followed by a unit
+                    if per || output.is_empty() {
+                        return None;
+                    }
+                    per = true;
+                }
+            }
+            _ => term.push(c),
+        }
+    }
+    match per {
+        true => None,
+        false => Some(output),
+    }
+}
+
+/// Return the siunitx macros of a unit with its prefix and its power, like
+/// "mm2" or "cm³"
+fn parse_term(term: &str) -> Option<String> {
+    let (symbol, power) = if let Some(symbol) = term.strip_suffix('²') {
+        (symbol, 2)
+    } else if let Some(symbol) = term.strip_suffix('³') {
+        (symbol, 3)
+    } else if let Some((symbol, power)) = term.split_once('^') {
+        (symbol, power.parse().ok()?)
+    } else {
+        let digits = term.len() - term.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        match digits {
+            0 => (term, 1),
+            _ => {
+                let (symbol, power) = term.split_at(term.len() - digits);
+                (symbol, power.parse().ok()?)
+            }
+        }
+    };
+    let unit = |symbol: &str| UNITS.iter().find(|(s, _)| *s == symbol).map(|(_, m)| *m);
+    let unit = match unit(symbol) {
+        Some(unit) => unit.to_string(),
+        None => PREFIXES.iter().find_map(|(prefix, prefix_macro)| {
+            let base = unit(symbol.strip_prefix(prefix)?)?;
+            Some(format!("{prefix_macro}{base}"))
+        })?,
+    };
+    let power = match power {
+        1 => String::new(),
+        2 => String::from("\\squared"),
+        3 => String::from("\\cubed"),
+        power => format!("\\tothe{{{power}}}"),
+    };
+    Some(format!("{unit}{power}"))
+}
@@ -1,4 +1,10 @@
-use grade::{ConfigXlsx, TabParameters};
+use grade::{
+    escape_latex, render_template, typeset_unit, typeset_value, Branding, CellValue, Config,
+    ConfigXlsx, GradeError, LabelMatcher, LabelMatching, PageLayout, PdfFile, Report,
+    ReportCategory, ReportCell, ReportProduct, SheetCache, TabParameters, TemplateScope,
+    TemplateValue,
+};
+use latex::{Document, Element};
 use strum::IntoEnumIterator;
 
 /// Test file
@@ -8,28 +14,118 @@ use strum::IntoEnumIterator;
 ///     - empty config
 ///
 
+#[test]
+fn test_config_arguments() {
+    let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| a.to_string()).collect() };
+    let config = Config::new(&args(&["grade", "--export", "config.json"])).unwrap();
+    assert_eq!(config.get_config_path(), "config.json");
+    assert!(config.is_export());
+    // without a config file, the default one is exported
+    let export_only = args(&["grade", "--export"]);
+    assert!(Config::new(&export_only).is_err());
+    let config = Config::default_from(&export_only);
+    assert_eq!(
+        config.get_config_path(),
+        Config::default().get_config_path()
+    );
+    assert!(config.is_export());
+    assert!(!Config::default_from(&args(&["grade"])).is_export());
+}
+
 #[test]
 fn test_search_cells_coordinates() {
     let config_xlsx = ConfigXlsx::default();
+    let mut sheets = SheetCache::new();
     for pdf_file in config_xlsx.pdf_file.iter() {
-        for (_, param) in TabParameters::iter().enumerate() {
-            let res = pdf_file.search_cells_coordinates(param);
-            assert_ne!(res, None);
+        let pdf_sheets = pdf_file.get_sheets(&mut sheets).unwrap();
+        for param in TabParameters::iter() {
+            let res = pdf_file.search_cells_coordinates(&pdf_sheets[0], param);
+            assert!(!res.unwrap().is_empty());
         }
     }
 }
 
+/// Check that every line of every tabular has as many cells as the tabular
+/// has columns, a multicolumn taking several of them
+fn assert_tables_fit(rendered: &str) {
+    for table in rendered.split("\\begin{tabularx}").skip(1) {
+        let table = &table[..table.find("\\end{tabularx}").unwrap()];
+        let spec_start = table.find("{X").unwrap();
+        let spec_end = spec_start + table[spec_start..].find('}').unwrap();
+        let nb_col = table[spec_start + 1..spec_end].split_whitespace().count();
+        for line in table[spec_end + 1..]
+            .lines()
+            .filter(|line| line.ends_with("\\\\"))
+        {
+            let width: usize = line
+                .split(" & ")
+                .map(|cell| {
+                    cell.trim_start()
+                        .strip_prefix("\\multicolumn{")
+                        .and_then(|rest| rest.split('}').next())
+                        .map_or(1, |span| span.parse().unwrap())
+                })
+                .sum();
+            assert_eq!(width, nb_col, "{line}");
+        }
+    }
+}
+
+/// Return the values of a product of a report, category by category
+fn product_values(report: &Report, product: &str) -> Vec<Vec<ReportCell>> {
+    report
+        .products
+        .iter()
+        .find(|p| p.name.text == product)
+        .unwrap()
+        .categories
+        .iter()
+        .map(|category| category.rows.iter().map(|row| row.value.clone()).collect())
+        .collect()
+}
+
+/// Return a pdf file reading BIOPLAST 400 in the Overview sheet of the
+/// anchors workbook, with `fields` set over the shared ones
+fn anchors_pdf_file(fields: serde_json::Value) -> PdfFile {
+    let mut config = serde_json::json!({
+        "pdfName": "anchors",
+        "output": "output/",
+        "source": "sources/anchors.xlsx",
+        "worksheet": "Overview",
+        "products": ["BIOPLAST 400"],
+        "categories": ["Properties", "Mechanical Properties"],
+        "parameters": ["Parameters", "Unit"]
+    });
+    for (field, value) in fields.as_object().unwrap() {
+        config[field] = value.clone();
+    }
+    serde_json::from_value(config).unwrap()
+}
+
 #[test]
 fn test_search_cells_coordinates_empty() {
     let config_xlsx = ConfigXlsx::new();
+    let mut sheets = SheetCache::new();
     for pdf_file in config_xlsx.pdf_file.iter() {
-        for (_, param) in TabParameters::iter().enumerate() {
-            let res = pdf_file.search_cells_coordinates(param);
-            assert_eq!(res, None);
+        let pdf_sheets = pdf_file.get_sheets(&mut sheets).unwrap();
+        for param in TabParameters::iter() {
+            let res = pdf_file.search_cells_coordinates(&pdf_sheets[0], param);
+            assert!(res.unwrap().is_empty());
         }
     }
 }
 
+#[test]
+fn test_sheet_loaded_once() {
+    let mut config_xlsx = ConfigXlsx::default();
+    config_xlsx.pdf_file.push(config_xlsx.pdf_file[0].clone());
+    let mut sheets = SheetCache::new();
+    for pdf_file in config_xlsx.pdf_file.iter() {
+        pdf_file.get_sheets(&mut sheets).unwrap();
+    }
+    assert_eq!(sheets.len(), 1);
+}
+
 // #[test]
 // fn test_search_cells_coordinates_empty() {
 //     let config_xlsx = ConfigXlsx::new();
@@ -40,3 +136,1213 @@ fn test_search_cells_coordinates_empty() {
 //         // }
 //     }
 // }
+
+#[test]
+fn test_search_products_in_several_sheets() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "several sheets",
+            "output": "output/",
+            "sheets": [
+                {"source": "sources/BIOTEC.xlsx", "worksheet": "Master - Rigid Overview "},
+                {"source": "sources/BIOTEC.xlsx", "worksheet": "Master - Flexible Overview"}
+            ],
+            "products": ["BIOPLAST 800", "BIOPLAST 107", "BIOPLAST 300"],
+            "categories": ["Properties"],
+            "parameters": ["Unit"]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let pdf_sheets = pdf_file.get_sheets(&mut sheets).unwrap();
+    let products = pdf_file
+        .search_sheets_coordinates(&pdf_sheets, TabParameters::Product)
+        .unwrap();
+    assert_eq!(products[0], vec![(7, 1)]);
+    assert_eq!(products[1], vec![(5, 1), (10, 1)]);
+}
+
+#[test]
+fn test_labels_missing_in_a_sheet() {
+    // every label is found, but not in each sheet with products
+    for (worksheet, product, missing) in [
+        ("Unnamed", "BIOPLAST 600", "Parameters"),
+        ("Untitled", "BIOPLAST 700", "Properties"),
+    ] {
+        let pdf_file: PdfFile = serde_json::from_value(serde_json::json!({
+            "pdfName": "several sheets",
+            "output": "output/",
+            "sheets": [
+                {"source": "sources/anchors.xlsx", "worksheet": "Overview"},
+                {"source": "sources/anchors.xlsx", "worksheet": worksheet}
+            ],
+            "products": ["BIOPLAST 400", product],
+            "categories": ["Properties"],
+            "parameters": ["Parameters"]
+        }))
+        .unwrap();
+        let mut sheets = SheetCache::new();
+        match pdf_file.extract_report(&pdf_file.get_sheets(&mut sheets).unwrap()) {
+            Err(GradeError::LabelNotFound { labels, sheets }) => {
+                assert_eq!(labels[0].label, missing);
+                assert_eq!(sheets[0].worksheet, worksheet);
+            }
+            other => panic!("{missing:?} should be missing in {worksheet:?}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_search_cells_coordinates_ods() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "ods",
+            "output": "output/",
+            "source": "sources/products.ods",
+            "worksheet": "Overview",
+            "products": ["BIOPLAST 500"],
+            "categories": ["Properties", "Mechanical Properties"],
+            "parameters": ["Parameters", "Unit"]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let categories = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Category)
+        .unwrap();
+    let end_categories = pdf_file.get_parameters_range(sheet, &categories);
+    assert_eq!(categories, vec![(0, 2), (0, 4)]);
+    assert_eq!(end_categories, vec![(0, 3), (0, 5)]);
+    let products = pdf_file.search_cells_coordinates(sheet, TabParameters::Product);
+    assert_eq!(products.unwrap(), vec![(4, 0)]);
+}
+
+#[test]
+fn test_search_cells_coordinates_csv() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "csv",
+            "output": "output/",
+            "source": "sources/products.csv",
+            "products": ["BIOPLAST 400", "BIOPLAST 500"],
+            "categories": ["Properties", "Mechanical Properties"],
+            "parameters": ["Parameters", "Unit"]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let categories = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Category)
+        .unwrap();
+    let end_categories = pdf_file.get_parameters_range(sheet, &categories);
+    assert_eq!(end_categories, vec![(0, 3), (0, 5)]);
+    let products = pdf_file.search_cells_coordinates(sheet, TabParameters::Product);
+    assert_eq!(products.unwrap(), vec![(3, 0), (4, 0)]);
+    let report = pdf_file
+        .extract_report(std::slice::from_ref(sheet))
+        .unwrap();
+    let values: Vec<Vec<CellValue>> = product_values(&report, "BIOPLAST 500")
+        .into_iter()
+        .map(|category| category.into_iter().map(|cell| cell.value).collect())
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            vec![CellValue::Number(1.3), CellValue::Number(4.0)],
+            vec![CellValue::Number(25.0), CellValue::Number(250.0)]
+        ]
+    );
+}
+
+#[test]
+fn test_label_not_found() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "missing",
+            "output": "output/",
+            "source": "sources/BIOTEC.xlsx",
+            "worksheet": "Master - Rigid Overview ",
+            "products": ["BIOPLAST 8000"],
+            "categories": [],
+            "parameters": []
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let pdf_sheets = pdf_file.get_sheets(&mut sheets).unwrap();
+    let res = pdf_file.search_sheets_coordinates(&pdf_sheets, TabParameters::Product);
+    match res {
+        Err(GradeError::LabelNotFound { labels, .. }) => {
+            assert_eq!(labels[0].label, "BIOPLAST 8000");
+            assert_eq!(labels[0].suggestions[0].0, "BIOPLAST 800");
+        }
+        _ => panic!("BIOPLAST 8000 should not be found"),
+    }
+}
+
+#[test]
+fn test_worksheet_not_found() {
+    let mut sheets = SheetCache::new();
+    let res = sheets.get("sources/BIOTEC.xlsx", "master - rigid overview");
+    match res {
+        Err(GradeError::Worksheet { suggestions, .. }) => {
+            assert_eq!(suggestions[0], "Master - Rigid Overview ");
+        }
+        _ => panic!("the worksheet name should not match"),
+    }
+}
+
+#[test]
+fn test_search_cells_coordinates_glob() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "glob",
+            "output": "output/",
+            "source": "sources/BIOTEC.xlsx",
+            "worksheet": "Master - Rigid Overview ",
+            "matching": "glob",
+            "products": ["BIOPLAST 8*"],
+            "categories": [],
+            "parameters": []
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let pdf_sheets = pdf_file.get_sheets(&mut sheets).unwrap();
+    let products = pdf_file
+        .search_sheets_coordinates(&pdf_sheets, TabParameters::Product)
+        .unwrap();
+    assert_eq!(products[0], vec![(7, 1), (13, 1)]);
+}
+
+#[test]
+fn test_label_matching() {
+    let trimmed = LabelMatcher::new("Material Density", LabelMatching::Trimmed).unwrap();
+    assert!(trimmed.is_match("Material Density "));
+    let insensitive = LabelMatcher::new("unit", LabelMatching::CaseInsensitive).unwrap();
+    assert!(insensitive.is_match(" Unit"));
+    let regex = LabelMatcher::new(r"BIOPLAST \d+", LabelMatching::Regex).unwrap();
+    assert!(regex.is_match("BIOPLAST 105"));
+    assert!(!regex.is_match("BIOPLAST 105/20"));
+    assert!(LabelMatcher::new("BIOPLAST (", LabelMatching::Regex).is_err());
+    assert!(!LabelMatcher::new("Unit", LabelMatching::Regex)
+        .unwrap()
+        .is_pattern());
+    assert!(!LabelMatcher::new("Unit", LabelMatching::Glob)
+        .unwrap()
+        .is_pattern());
+    assert!(LabelMatcher::new("Unit*", LabelMatching::Glob)
+        .unwrap()
+        .is_pattern());
+}
+
+#[test]
+fn test_search_cells_coordinates_anchors() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "anchors",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Overview",
+            "products": [{"name": "Products"}],
+            "categories": [{"cell": "C1"}, {"name": "MechanicalHeader"}],
+            "parameters": [{"cell": "'Overview'!B2"}, "Unit"]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let categories = pdf_file.search_cells_coordinates(sheet, TabParameters::Category);
+    assert_eq!(categories.unwrap(), vec![(0, 2), (0, 4)]);
+    let parameters = pdf_file.search_cells_coordinates(sheet, TabParameters::Parameter);
+    assert_eq!(parameters.unwrap(), vec![(1, 1), (2, 1)]);
+    let products = pdf_file.search_cells_coordinates(sheet, TabParameters::Product);
+    assert_eq!(products.unwrap(), vec![(3, 0), (4, 0)]);
+}
+
+#[test]
+fn test_search_cells_coordinates_table() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "table",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Overview",
+            "products": [{"table": "Grades", "column": "Grade"}],
+            "categories": [],
+            "parameters": []
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let products = pdf_file.search_cells_coordinates(sheet, TabParameters::Product);
+    assert_eq!(products.unwrap(), vec![(3, 0), (4, 0)]);
+}
+
+#[test]
+fn test_search_cells_coordinates_scoped() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "scoped",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Blocks",
+            "products": [],
+            "categories": [],
+            "parameters": [
+                {"label": "Unit", "within": "Mechanical Properties"},
+                {"label": "Unit", "range": "B1:C4"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let parameters = pdf_file.search_cells_coordinates(sheet, TabParameters::Parameter);
+    assert_eq!(parameters.unwrap(), vec![(2, 1), (2, 3)]);
+
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "duplicate",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Blocks",
+            "products": [],
+            "categories": [],
+            "parameters": ["Unit"]
+        }"#,
+    )
+    .unwrap();
+    match pdf_file.search_cells_coordinates(sheet, TabParameters::Parameter) {
+        Err(GradeError::DuplicateLabel { label, cells }) => {
+            assert_eq!(label, "Unit");
+            assert_eq!(cells.len(), 2);
+        }
+        other => panic!("expected a duplicate label, got {other:?}"),
+    }
+    // a plain label is not a pattern whatever the matching
+    for matching in ["glob", "regex"] {
+        let pdf_file: PdfFile = serde_json::from_value(serde_json::json!({
+            "pdfName": "duplicate",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Blocks",
+            "matching": matching,
+            "products": [],
+            "categories": [],
+            "parameters": ["Unit"]
+        }))
+        .unwrap();
+        assert!(matches!(
+            pdf_file.search_cells_coordinates(sheet, TabParameters::Parameter),
+            Err(GradeError::DuplicateLabel { .. })
+        ));
+    }
+}
+
+#[test]
+fn test_scope_not_found() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "missing scope",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Blocks",
+            "products": [],
+            "categories": [],
+            "parameters": [{"label": "Density", "within": "Thermal Properties"}]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let pdf_sheets = pdf_file.get_sheets(&mut sheets).unwrap();
+    match pdf_file.search_sheets_coordinates(&pdf_sheets, TabParameters::Parameter) {
+        Err(GradeError::ScopeNotFound { label, category }) => {
+            assert_eq!(label, "Density within \"Thermal Properties\"");
+            assert_eq!(category, "Thermal Properties");
+        }
+        other => panic!("expected a missing scope, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_ambiguous_scope() {
+    // "Unit" is written under both categories
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "ambiguous scope",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Blocks",
+            "products": [],
+            "categories": [],
+            "parameters": [{"label": "Density", "within": "Unit"}]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let pdf_sheets = pdf_file.get_sheets(&mut sheets).unwrap();
+    match pdf_file.search_sheets_coordinates(&pdf_sheets, TabParameters::Parameter) {
+        Err(GradeError::AmbiguousScope { category, cells }) => {
+            assert_eq!(category, "Unit");
+            assert_eq!(cells.len(), 2);
+        }
+        other => panic!("expected an ambiguous scope, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_get_parameters_range_merged() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "merged",
+            "output": "output/",
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Merged",
+            "products": [],
+            "categories": ["Properties", "Mechanical Properties"],
+            "parameters": []
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let categories = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Category)
+        .unwrap();
+    assert_eq!(categories, vec![(0, 1), (0, 3)]);
+    // the stray text under the merged header does not cut the category
+    assert_eq!(
+        pdf_file.get_parameters_range(sheet, &categories),
+        vec![(0, 2), (0, 5)]
+    );
+}
+
+#[test]
+fn test_format_values() {
+    let pdf_file = anchors_pdf_file(serde_json::json!({
+        "worksheet": "Values",
+        "categories": ["Properties"],
+        "numberFormat": {"thousandsSeparator": ","},
+        "formats": {
+            "MPa": {"decimals": 1, "thousandsSeparator": " "},
+            "MFR": {"decimals": 2},
+            "Updated": {"dateFormat": "%d/%m/%Y"}
+        }
+    }));
+    let report = pdf_file.get_report(&mut SheetCache::new()).unwrap();
+    let values = product_values(&report, "BIOPLAST 400");
+    assert_eq!(values[0][3].value, CellValue::Bool(true));
+    let texts: Vec<&str> = values[0].iter().map(|cell| cell.text.as_str()).collect();
+    assert_eq!(
+        texts,
+        vec![
+            "1.25",
+            "3.00",
+            "12 345.7",
+            "true",
+            "15/03/2023",
+            "1,234,567"
+        ]
+    );
+}
+
+#[test]
+fn test_error_cells() {
+    let config = |policy: &str| -> PdfFile {
+        anchors_pdf_file(serde_json::json!({
+            "worksheet": "Values",
+            "products": ["BIOPLAST 500"],
+            "categories": ["Properties"],
+            "errorCells": policy,
+            "errorPlaceholder": "--"
+        }))
+    };
+    let mut sheets = SheetCache::new();
+    let pdf_file = config("placeholder");
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let categories = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Category)
+        .unwrap();
+    let parameters = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Parameter)
+        .unwrap();
+    let end_categories = pdf_file.get_parameters_range(sheet, &categories);
+    let mut texts = |policy: &str| -> Vec<String> {
+        let report = config(policy).get_report(&mut sheets).unwrap();
+        product_values(&report, "BIOPLAST 500")[0][..2]
+            .iter()
+            .map(|cell| cell.text.to_string())
+            .collect()
+    };
+    assert_eq!(texts("placeholder"), ["--", "--"]);
+    assert_eq!(texts("blank"), ["", ""]);
+
+    let error_cells = config("warn")
+        .get_error_cells(sheet, (4, 0), &categories, &end_categories, &parameters)
+        .unwrap();
+    let cells: Vec<(usize, usize)> = error_cells.iter().map(|c| c.address.cell).collect();
+    assert_eq!(cells, vec![(4, 1), (4, 2)]);
+    assert_eq!(error_cells[0].error, "#N/A");
+
+    match config("fail").get_error_cells(sheet, (4, 0), &categories, &end_categories, &parameters) {
+        Err(GradeError::ErrorCell(cell)) => assert_eq!(cell.address.cell, (4, 1)),
+        other => panic!("expected an error cell, got {other:?}"),
+    }
+
+    // only the error cells written in the document are published
+    let mut published = |policy: &str| {
+        config(policy)
+            .get_report(&mut sheets)
+            .unwrap()
+            .error_cells()
+            .len()
+    };
+    assert_eq!(published("placeholder"), 2);
+    assert_eq!(published("blank"), 0);
+
+    // the cells of a row left out for its empty value are not published
+    let mut report = config("blank").get_report(&mut sheets).unwrap();
+    let row = &mut report.products[0].categories[0].rows[0];
+    row.parameters[1] = ReportCell {
+        text: String::from("--"),
+        ..row.value.clone()
+    };
+    assert_eq!(report.error_cells(), vec![]);
+}
+
+#[test]
+fn test_search_cells_coordinates_transposed() {
+    let pdf_file = anchors_pdf_file(serde_json::json!({
+        "worksheet": "Transposed",
+        "orientation": "columns",
+        "products": ["BIOPLAST 400", {"cell": "E1"}]
+    }));
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let categories = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Category)
+        .unwrap();
+    let end_categories = pdf_file.get_parameters_range(sheet, &categories);
+    assert_eq!(end_categories, vec![(0, 3), (0, 5)]);
+    let products = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Product)
+        .unwrap();
+    assert_eq!(products, vec![(3, 0), (4, 0)]);
+    // the addresses are the ones of the worksheet
+    assert_eq!(sheet.address(products[1]).cell, (0, 4));
+    let report = pdf_file
+        .extract_report(std::slice::from_ref(sheet))
+        .unwrap();
+    let values = product_values(&report, "BIOPLAST 500");
+    let cells: Vec<Vec<(CellValue, (usize, usize))>> = values
+        .into_iter()
+        .map(|category| {
+            category
+                .into_iter()
+                .map(|cell| (cell.value, cell.address.unwrap().cell))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        cells,
+        vec![
+            vec![
+                (CellValue::Number(1.3), (2, 4)),
+                (CellValue::Number(4.0), (3, 4))
+            ],
+            vec![
+                (CellValue::Number(25.0), (4, 4)),
+                (CellValue::Number(250.0), (5, 4))
+            ]
+        ]
+    );
+}
+
+#[test]
+fn test_get_parameter_groups() {
+    let pdf_file = anchors_pdf_file(serde_json::json!({
+        "worksheet": "Headers",
+        "subParameters": "Direction"
+    }));
+    let mut sheets = SheetCache::new();
+    let sheet = &pdf_file.get_sheets(&mut sheets).unwrap()[0];
+    let categories = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Category)
+        .unwrap();
+    let parameters = pdf_file
+        .search_cells_coordinates(sheet, TabParameters::Parameter)
+        .unwrap();
+    let end_categories = pdf_file.get_parameters_range(sheet, &categories);
+    let groups = pdf_file
+        .get_parameter_groups(sheet, &categories, &end_categories, &parameters)
+        .unwrap();
+    let group = |name: &str, sub_name: &str| Some((name.to_string(), sub_name.to_string()));
+    assert_eq!(
+        groups,
+        vec![
+            vec![None, None],
+            vec![
+                group("Tensile strength", "MD"),
+                group("Tensile strength", "TD"),
+                group("Elongation", "MD"),
+                group("Elongation", "TD"),
+            ]
+        ]
+    );
+}
+
+#[test]
+fn test_parameter_groups_of_stacked_categories() {
+    let pdf_file = anchors_pdf_file(serde_json::json!({
+        "worksheet": "Stacked",
+        "categories": ["Mechanical Properties", "Tear resistance"],
+        "subParameters": "Direction"
+    }));
+    let mut sheets = SheetCache::new();
+    let report = pdf_file
+        .extract_report(&pdf_file.get_sheets(&mut sheets).unwrap())
+        .unwrap();
+    // the category below the first one has its headers at the same offsets
+    let tear = &report.products[0].categories[1];
+    assert_eq!(tear.title.text, "Tear resistance");
+    let names: Vec<(Option<&str>, &str, &str)> = tear
+        .rows
+        .iter()
+        .map(|row| {
+            (
+                row.group.as_deref(),
+                row.parameters[0].text.as_str(),
+                row.value.text.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![(Some("Tear"), "MD", "40"), (Some("Tear"), "TD", "38")]
+    );
+    let rendered = latex::print(&ConfigXlsx::default().render(&report).unwrap()).unwrap();
+    assert!(rendered.contains("\\multicolumn{3}{l}{\\textbf{Tear}}"));
+    assert_tables_fit(&rendered);
+}
+
+#[test]
+fn test_extract_report() {
+    let pdf_file = anchors_pdf_file(serde_json::json!({"products": ["BIOPLAST 500"]}));
+    let mut sheets = SheetCache::new();
+    let report = pdf_file
+        .extract_report(&pdf_file.get_sheets(&mut sheets).unwrap())
+        .unwrap();
+    assert_eq!(report.product_names(), vec!["BIOPLAST 500".to_string()]);
+    let mechanical = &report.products[0].categories[1];
+    assert_eq!(mechanical.title.text, "Mechanical Properties");
+    let row = &mechanical.rows[0];
+    assert_eq!(row.parameters[0].text, "Tensile strength");
+    assert_eq!(row.parameters[1].text, "MPa");
+    assert_eq!(row.value.value, CellValue::Number(25.0));
+    assert_eq!(row.value.address.as_ref().unwrap().cell, (4, 4));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(
+        json["products"][0]["categories"][1]["rows"][0]["value"]["address"]["worksheet"],
+        "Overview"
+    );
+
+    // the renderer only needs the report
+    let page = ConfigXlsx::default().render(&report).unwrap();
+    let rendered = latex::print(&page).unwrap();
+    assert!(rendered.contains("BIOPLAST 500"));
+    assert!(rendered.contains("Tensile strength & 25 & \\si{\\mega\\pascal}"));
+    assert_tables_fit(&rendered);
+}
+
+#[test]
+fn test_export_report() {
+    let pdf_file = anchors_pdf_file(serde_json::json!({
+        "pdfName": "export",
+        "output": "target/test-export/"
+    }));
+    let mut sheets = SheetCache::new();
+    let report = pdf_file
+        .extract_report(&pdf_file.get_sheets(&mut sheets).unwrap())
+        .unwrap();
+    let paths = pdf_file.export_report(&report).unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_reader(std::fs::File::open(&paths[0]).unwrap()).unwrap();
+    assert_eq!(json["products"][0]["name"]["text"], "BIOPLAST 400");
+    let csv = std::fs::read_to_string(&paths[1]).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "product,category,parameter,value,unit,source cell"
+    );
+    assert_eq!(
+        lines[1],
+        "BIOPLAST 400,Properties,Density,1.25,g/cm³,'[sources/anchors.xlsx]Overview'!C4"
+    );
+    assert_eq!(lines.len(), 5);
+}
+
+#[test]
+fn test_get_report_from_data_files() {
+    let mut sheets = SheetCache::new();
+    let toml_file: PdfFile = serde_json::from_str(
+        r#"{"pdfName": "lims", "output": "output/", "data": "sources/lims.toml"}"#,
+    )
+    .unwrap();
+    let report = toml_file.get_report(&mut sheets).unwrap();
+    assert_eq!(report.pdf_name, "lims");
+    let json_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "lims",
+            "output": "output/",
+            "data": "sources/lims.json",
+            "products": ["BIOPLAST 600"]
+        }"#,
+    )
+    .unwrap();
+    // both files hold the same product, and no sheet is loaded
+    assert_eq!(json_file.get_report(&mut sheets).unwrap(), report);
+    assert!(sheets.is_empty());
+    let density = &report.products[0].categories[0].rows[0].value;
+    assert_eq!(density.text, "1.27");
+    assert_eq!(density.value, CellValue::Number(1.27));
+
+    let rendered = latex::print(&ConfigXlsx::default().render(&report).unwrap()).unwrap();
+    assert!(rendered.contains("\\multicolumn{3}{l}{\\textbf{Tensile strength}}"));
+    assert!(rendered.contains("{X l  l }"));
+    assert!(rendered.contains("MD & 22 & \\si{\\mega\\pascal}"));
+    assert_tables_fit(&rendered);
+
+    let missing: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "lims",
+            "output": "output/",
+            "data": "sources/lims.json",
+            "products": ["BIOPLAST 60"]
+        }"#,
+    )
+    .unwrap();
+    match missing.get_report(&mut sheets) {
+        Err(GradeError::LabelNotFound { labels, .. }) => {
+            assert_eq!(labels[0].suggestions[0].0, "BIOPLAST 600")
+        }
+        other => panic!("expected a label not found, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_empty_labels() {
+    // the labels are only optional for the data files and the databases
+    let pdf_file = anchors_pdf_file(serde_json::json!({"parameters": []}));
+    match pdf_file.get_report(&mut SheetCache::new()) {
+        Err(GradeError::EmptyLabels { field, .. }) => assert_eq!(field, "parameters"),
+        other => panic!("expected empty labels, got {other:?}"),
+    }
+
+    let report: Report = serde_json::from_str(
+        r#"{"products": [{"name": "GRADE", "categories": [{"title": "Empty", "headers": [], "rows": []}]}]}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        ConfigXlsx::default().render(&report),
+        Err(GradeError::Table { .. })
+    ));
+}
+
+#[test]
+fn test_check_data_files() {
+    let categories = [
+        r#"{"title": "Empty", "headers": ["Parameters", "Unit"], "rows": []}"#,
+        r#"{"title": "Empty", "headers": [], "rows": [{"parameters": [], "value": 1}]}"#,
+        r#"{"title": "Short", "headers": ["Parameters", "Unit"], "rows": [{"parameters": ["Density"], "value": 1}]}"#,
+        r#"{"title": "Unnamed", "headers": ["Parameters", "Unit"], "rows": [{"parameters": ["", "%"], "value": 1}]}"#,
+    ];
+    let path = std::env::temp_dir().join("grade_check_data_file.json");
+    for category in categories {
+        let data = format!(r#"{{"products": [{{"name": "GRADE", "categories": [{category}]}}]}}"#);
+        std::fs::write(&path, data).unwrap();
+        let pdf_file: PdfFile = serde_json::from_value(serde_json::json!({
+            "pdfName": "check",
+            "output": "output/",
+            "data": path.to_str().unwrap()
+        }))
+        .unwrap();
+        match pdf_file.get_report(&mut SheetCache::new()) {
+            Err(GradeError::DataFile { .. }) => (),
+            other => panic!("expected a data file error for {category}, got {other:?}"),
+        }
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_get_report_from_database() {
+    let mut sheets = SheetCache::new();
+    let database_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "lab",
+            "output": "output/",
+            "database": {
+                "path": "sources/lab.sqlite",
+                "query": "SELECT grade, category, test AS Parameters, direction, unit AS Unit, result FROM results WHERE result IS NOT NULL",
+                "columns": {
+                    "product": "grade",
+                    "parameter": "Parameters",
+                    "value": "result",
+                    "unit": "Unit",
+                    "group": "direction"
+                }
+            },
+            "products": ["BIOPLAST 600"]
+        }"#,
+    )
+    .unwrap();
+    let report = database_file.get_report(&mut sheets).unwrap();
+    assert!(sheets.is_empty());
+    assert_eq!(report.product_names(), vec!["BIOPLAST 600"]);
+    let categories = &report.products[0].categories;
+    assert_eq!(categories.len(), 2);
+    assert_eq!(
+        categories[0].parameter_texts(),
+        vec!["Density", "g/cm³", "MFR", "g/10 min"]
+    );
+    let density = &categories[0].rows[0].value;
+    assert_eq!(density.value, CellValue::Number(1.27));
+    assert_eq!(density.address.as_ref().unwrap().cell, (1, 5));
+    assert_eq!(
+        categories[1].rows[1].group.as_deref(),
+        Some("Tensile strength")
+    );
+
+    let rendered = latex::print(&ConfigXlsx::default().render(&report).unwrap()).unwrap();
+    assert!(rendered.contains("\\multicolumn{3}{l}{\\textbf{Tensile strength}}"));
+    assert!(rendered.contains("TD & 19 & \\si{\\mega\\pascal}"));
+    // a column per parameter column, and one for the value
+    assert!(rendered.contains("{X l  l }"));
+    assert!(!rendered.contains("{X l }"));
+    assert_tables_fit(&rendered);
+
+    let wrong_column: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "lab",
+            "output": "output/",
+            "database": {"path": "sources/lab.sqlite", "table": "results"}
+        }"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        wrong_column.get_report(&mut sheets),
+        Err(GradeError::Database { .. })
+    ));
+}
+
+#[test]
+fn test_join_lookup() {
+    let lookup = |fields: &[&str]| {
+        serde_json::json!({
+            "source": "sources/anchors.xlsx",
+            "worksheet": "Products",
+            "key": "Product name",
+            "fields": fields
+        })
+    };
+    let pdf_file = anchors_pdf_file(serde_json::json!({
+        "products": ["BIOPLAST 400", "BIOPLAST 500"],
+        "lookup": lookup(&["Certification", "Description"])
+    }));
+    let mut sheets = SheetCache::new();
+    let report = pdf_file.get_report(&mut sheets).unwrap();
+    let fields = &report.products[0].fields;
+    let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+    assert_eq!(names, vec!["Certification", "Description"]);
+    assert_eq!(fields[0].value.text, "DIN-7W0123");
+    assert_eq!(fields[0].value.address.as_ref().unwrap().cell, (1, 3));
+    // the error cell of the second product is reported like the other cells
+    let errors = report.error_cells();
+    assert!(errors
+        .iter()
+        .any(|cell| cell.address.worksheet == "Products" && cell.error == "#N/A"));
+
+    let rendered = latex::print(&ConfigXlsx::default().render(&report).unwrap()).unwrap();
+    assert!(rendered.contains("BIOPLAST 400\\\\\nCertification: DIN-7W0123\\\\\n"));
+
+    let missing = anchors_pdf_file(serde_json::json!({"lookup": lookup(&["Descriptions"])}));
+    match missing.get_report(&mut sheets) {
+        Err(GradeError::LabelNotFound { labels, .. }) => {
+            assert_eq!(labels[0].suggestions[0].0, "Description")
+        }
+        other => panic!("expected a missing field, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_add_variants() {
+    let pdf_file = anchors_pdf_file(serde_json::json!({
+        "worksheet": "Variants",
+        "products": ["BIOPLAST 400", "BIOPLAST 400 HF", "BIOPLAST 500"],
+        "variants": [
+            {
+                "name": "BIOPLAST 400 HF",
+                "base": "BIOPLAST 400",
+                "overrides": {"Elongation": 350},
+                "highlight": true
+            },
+            {"name": "BIOPLAST 500 LD", "base": "BIOPLAST 500", "overrides": {"Density": 1.1}}
+        ]
+    }));
+    let mut sheets = SheetCache::new();
+    let report = pdf_file.get_report(&mut sheets).unwrap();
+    assert_eq!(
+        report.product_names(),
+        vec![
+            "BIOPLAST 400",
+            "BIOPLAST 500",
+            "BIOPLAST 500 LD",
+            "BIOPLAST 400 HF"
+        ]
+    );
+    // the sparse row of the variant and its overrides change two values
+    let variant = &report.products[3];
+    let values: Vec<(&str, bool)> = variant
+        .categories
+        .iter()
+        .flat_map(|category| category.rows.iter())
+        .map(|row| (row.value.text.as_str(), row.highlighted))
+        .collect();
+    assert_eq!(
+        values,
+        vec![("1.25", false), ("8", true), ("20", false), ("350", true)]
+    );
+    assert_eq!(
+        variant.categories[0].rows[1]
+            .value
+            .address
+            .as_ref()
+            .unwrap()
+            .cell,
+        (5, 3)
+    );
+    let low_density = &report.products[2].categories[0].rows[0];
+    assert_eq!(low_density.value.text, "1.1");
+    assert!(!low_density.highlighted);
+
+    let rendered = latex::print(&ConfigXlsx::default().render(&report).unwrap()).unwrap();
+    assert!(rendered.contains("MFR & \\cellcolor{highlight_color}8 & g/10 min"));
+
+    let missing = anchors_pdf_file(serde_json::json!({
+        "worksheet": "Variants",
+        "variants": [{"name": "BIOPLAST 400 HF", "base": "BIOPLAST 400", "overrides": {"Densty": 1.1}}]
+    }));
+    match missing.get_report(&mut sheets) {
+        Err(GradeError::LabelNotFound { labels, .. }) => {
+            assert_eq!(labels[0].suggestions[0].0, "Density")
+        }
+        other => panic!("expected a missing parameter, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_add_variants_order_and_overrides() {
+    let report: Report = serde_json::from_str(
+        r#"{"products": [
+            {"name": "BIOPLAST 600", "categories": [
+                {"title": "Properties", "headers": ["Parameters"], "rows": [
+                    {"parameters": ["Density"], "value": 1.27},
+                    {"parameters": ["MFR"], "value": 6}
+                ]},
+                {"title": "Film Properties", "headers": ["Parameters"], "rows": [
+                    {"parameters": ["Density"], "value": 1.3}
+                ]}
+            ]},
+            {"name": "BIOPLAST 700", "categories": []}
+        ]}"#,
+    )
+    .unwrap();
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "variants",
+            "output": "output/",
+            "variants": [
+                {"name": "BIOPLAST 600 A", "base": "BIOPLAST 600", "overrides": {"MFR": 6}, "highlight": true},
+                {"name": "BIOPLAST 600 B", "base": "BIOPLAST 600", "overrides": {"MFR": 7}, "highlight": true},
+                {"name": "BIOPLAST 600 C", "base": "BIOPLAST 600"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let mut products = report.products.clone();
+    pdf_file.add_variants(&mut products).unwrap();
+    // the variants of a base keep the order of the config
+    let names: Vec<&str> = products.iter().map(|p| p.name.text.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "BIOPLAST 600",
+            "BIOPLAST 600 A",
+            "BIOPLAST 600 B",
+            "BIOPLAST 600 C",
+            "BIOPLAST 700"
+        ]
+    );
+    // an override with the value of the base changes nothing
+    let mfr = |i: usize| &products[i].categories[0].rows[1];
+    assert!(!mfr(1).highlighted);
+    assert_eq!(mfr(2).value.text, "7");
+    assert!(mfr(2).highlighted);
+
+    // "Density" is in both categories
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{
+            "pdfName": "variants",
+            "output": "output/",
+            "variants": [{"name": "BIOPLAST 600 LD", "base": "BIOPLAST 600", "overrides": {"Density": 1.1}}]
+        }"#,
+    )
+    .unwrap();
+    match pdf_file.add_variants(&mut report.products.clone()) {
+        Err(GradeError::AmbiguousOverride { categories, .. }) => {
+            assert_eq!(categories, vec!["Properties", "Film Properties"])
+        }
+        other => panic!("expected an ambiguous override, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_branding() {
+    let config_xlsx: ConfigXlsx = serde_json::from_str(
+        r#"{
+            "colorText": [13, 64, 47],
+            "colorTabTitle": [237, 233, 230],
+            "colorTabLine": [215, 212, 210],
+            "assets": "resources",
+            "marginSize": 0.75,
+            "alignmentTabular": "left",
+            "branding": {"author": "Acme Polymers", "logo": "acme", "logoScale": 0.5},
+            "pdfFile": [
+                {
+                    "pdfName": "private label",
+                    "output": "output/",
+                    "data": "sources/lims.json",
+                    "branding": {"logo": "", "heading": "Product Data Sheet", "disclaimer": ""}
+                },
+                {"pdfName": "acme", "output": "output/", "data": "sources/lims.json"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let mut sheets = SheetCache::new();
+    let private_label = &config_xlsx.pdf_file[0];
+    let branding = config_xlsx.get_branding(private_label);
+    assert_eq!(branding.author.as_deref(), Some("Acme Polymers"));
+    let report = private_label.get_report(&mut sheets).unwrap();
+    let rendered = latex::print(
+        &config_xlsx
+            .render_with_branding(&report, &branding)
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(!rendered.contains("includegraphics"));
+    assert!(rendered.contains("\\textbf{Product Data Sheet}"));
+    assert!(!rendered.contains("Disclaimer"));
+    // the footer is still the default one
+    assert!(rendered.contains("BIOTEC Biologische Naturverpackungen"));
+
+    let acme = &config_xlsx.pdf_file[1];
+    let rendered = latex::print(
+        &config_xlsx
+            .render_with_branding(&report, &config_xlsx.get_branding(acme))
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(rendered.contains("\\includegraphics[scale=0.5]{acme}"));
+    assert!(rendered.contains("\\textbf{Preliminary Data Sheet}"));
+}
+
+#[test]
+fn test_render_template() {
+    let mut scope = TemplateScope::from([(
+        String::from("title"),
+        TemplateValue::Text(String::from("Data")),
+    )]);
+    let items = ["A", "B"]
+        .iter()
+        .map(|name| {
+            TemplateScope::from([(String::from("name"), TemplateValue::Text(name.to_string()))])
+        })
+        .collect();
+    scope.insert(String::from("items"), TemplateValue::List(items));
+    let filled = render_template(
+        "\\section{<<title>>}<<#items>>\\item{<<name>> of <<title>>}<</items>><<^none>>!<</none>>",
+        &scope,
+    )
+    .unwrap();
+    assert_eq!(filled, "\\section{Data}\\item{A of Data}\\item{B of Data}!");
+    assert!(render_template("<<#items>>", &scope).is_err());
+    assert!(render_template("<<missing>>", &scope).is_err());
+
+    let config_xlsx: ConfigXlsx = serde_json::from_str(
+        r#"{
+            "colorText": [13, 64, 47],
+            "colorTabTitle": [237, 233, 230],
+            "colorTabLine": [215, 212, 210],
+            "assets": "resources",
+            "marginSize": 0.75,
+            "alignmentTabular": "left",
+            "template": "templates/datasheet.tex",
+            "pdfFile": [{"pdfName": "lims", "output": "output/", "data": "sources/lims.json"}]
+        }"#,
+    )
+    .unwrap();
+    let pdf_file = &config_xlsx.pdf_file[0];
+    let report = pdf_file.get_report(&mut SheetCache::new()).unwrap();
+    let rendered = config_xlsx.render_tex(&report, pdf_file).unwrap();
+    assert!(rendered.starts_with("\\documentclass{article}"));
+    assert!(rendered.contains("\\item BIOPLAST 700\\\\"));
+    assert!(rendered.contains("MD & 22 & \\si{\\mega\\pascal}"));
+    assert!(!rendered.contains("<<"));
+}
+
+/// A layout without cover page, writing the tables of every product on a
+/// single page
+struct CompactLayout {
+    config: ConfigXlsx,
+}
+
+impl PageLayout for CompactLayout {
+    fn preamble(&self, page: &mut Document, branding: &Branding) {
+        self.config.preamble(page, branding);
+    }
+
+    fn cover_page(&self, _page: &mut Document, _report: &Report, _branding: &Branding) {}
+
+    fn product_page(
+        &self,
+        page: &mut Document,
+        product: &ReportProduct,
+        _branding: &Branding,
+    ) -> Result<(), GradeError> {
+        page.push(Element::UserDefined(format!(
+            "\\section*{{{}}}",
+            product.name.text
+        )));
+        for category in product.categories.iter() {
+            self.category_table(page, category)?;
+        }
+        Ok(())
+    }
+
+    fn category_table(
+        &self,
+        page: &mut Document,
+        category: &ReportCategory,
+    ) -> Result<(), GradeError> {
+        self.config.category_table(page, category)
+    }
+}
+
+#[test]
+fn test_page_layout() {
+    let pdf_file: PdfFile = serde_json::from_str(
+        r#"{"pdfName": "compact", "output": "output/", "data": "sources/lims.json"}"#,
+    )
+    .unwrap();
+    let report = pdf_file.get_report(&mut SheetCache::new()).unwrap();
+    let layout = CompactLayout {
+        config: ConfigXlsx::default(),
+    };
+    let rendered = latex::print(&layout.compose(&report, &Branding::default()).unwrap()).unwrap();
+    assert!(rendered.contains("\\section*{BIOPLAST 600}"));
+    assert!(rendered.contains("MD & 22 & \\si{\\mega\\pascal}"));
+    assert!(!rendered.contains("Contents"));
+    assert!(!rendered.contains("clearpage"));
+    assert_tables_fit(&rendered);
+}
+
+#[test]
+fn test_escape_latex() {
+    assert_eq!(
+        escape_latex("R&D_1 {50%} #2 $3 ~ ^ \\"),
+        "R\\&D\\_1 \\{50\\%\\} \\#2 \\$3 \\textasciitilde{} \\textasciicircum{} \\textbackslash{}"
+    );
+    assert_eq!(escape_latex("<0,15"), "\\(<\\) 0,15");
+    assert_eq!(escape_latex("10 µm"), "10 \\(\\mu\\)m");
+    assert_eq!(escape_latex("PM2.5"), "PM2.5");
+
+    let report: Report = serde_json::from_str(
+        r#"{
+            "products": [{
+                "name": "GRADE_1 & CO",
+                "categories": [{
+                    "title": "Properties #1",
+                    "headers": ["Parameters", "Unit"],
+                    "rows": [
+                        {"parameters": ["Share", "%"], "value": "~50"},
+                        {"group": "Tensile_strength", "parameters": ["MD", "MPa"], "value": 22}
+                    ]
+                }]
+            }]
+        }"#,
+    )
+    .unwrap();
+    let rendered = latex::print(&ConfigXlsx::default().render(&report).unwrap()).unwrap();
+    assert!(rendered.contains("\\item GRADE\\_1 \\& CO"));
+    assert!(rendered.contains("Properties \\#1"));
+    assert!(rendered.contains("Share & \\textasciitilde{}50 & \\si{\\percent}"));
+    assert!(rendered.contains("\\textbf{Tensile\\_strength}"));
+    assert!(!rendered.contains("GRADE_1"));
+}
+
+#[test]
+fn test_typeset_units() {
+    assert_eq!(typeset_unit("g/m²"), "\\si{\\gram\\per\\metre\\squared}");
+    assert_eq!(typeset_unit("cm3"), "\\si{\\centi\\metre\\cubed}");
+    assert_eq!(
+        typeset_unit("N/mm2"),
+        "\\si{\\newton\\per\\milli\\metre\\squared}"
+    );
+    assert_eq!(typeset_unit("°C"), "\\si{\\degreeCelsius}");
+    assert_eq!(typeset_unit("%"), "\\si{\\percent}");
+    assert_eq!(
+        typeset_unit("KJ/m²"),
+        "\\si{\\kilo\\joule\\per\\metre\\squared}"
+    );
+    assert_eq!(typeset_unit("g/10 min"), "g/10 min");
+    assert_eq!(typeset_unit("weight %"), "weight \\%");
+
+    assert_eq!(typeset_value("20 ± 2"), "\\num{20 +- 2}");
+    assert_eq!(typeset_value("1.25+/-0.02"), "\\num{1.25 +- 0.02}");
+    assert_eq!(
+        typeset_value("1,25 ± 0,02"),
+        "\\num[output-decimal-marker={,}]{1,25 +- 0,02}"
+    );
+    assert_eq!(typeset_value("<0,15"), "\\(<\\) 0,15");
+
+    let report: Report = serde_json::from_str(
+        r#"{
+            "products": [{
+                "name": "BIOPLAST 600",
+                "categories": [{
+                    "title": "Properties",
+                    "headers": ["Parameters", "Unit"],
+                    "rows": [
+                        {"parameters": ["Density", "g/cm³"], "value": "1.25 ± 0.02"},
+                        {"parameters": ["Grade m2", ""], "value": "20 ± 2"}
+                    ]
+                }]
+            }]
+        }"#,
+    )
+    .unwrap();
+    let rendered = latex::print(&ConfigXlsx::default().render(&report).unwrap()).unwrap();
+    assert!(rendered.contains("\\usepackage{siunitx}"));
+    assert!(
+        rendered.contains("Density & \\num{1.25 +- 0.02} & \\si{\\gram\\per\\centi\\metre\\cubed}")
+    );
+    // only the cells of the unit column are typeset
+    assert!(rendered.contains("Grade m2 & 20 ± 2 & "));
+}